@@ -0,0 +1,340 @@
+use crate::bounds::Bounds;
+use crate::point::Point2d;
+use crate::polygon::Polygon;
+use crate::segment::Segment;
+
+/// Affine map of the plane:
+///
+/// x' = a * x + b * y + c
+/// y' = d * x + e * y + f
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Affine2 {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+impl Affine2 {
+    pub fn identity() -> Self {
+        Self::scale(1.0, 1.0)
+    }
+
+    pub fn translation(dx: f64, dy: f64) -> Self {
+        Self {
+            c: dx,
+            f: dy,
+            ..Self::identity()
+        }
+    }
+
+    /// Rotation by `angle` radians around the origin, counter-clockwise when y points up (and
+    /// therefore clockwise in the y-down system [`Polygon`] is defined in)
+    pub fn rotation(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self {
+            a: cos,
+            b: -sin,
+            c: 0.0,
+            d: sin,
+            e: cos,
+            f: 0.0,
+        }
+    }
+
+    /// Rotation by `angle` radians around `center`
+    pub fn rotation_around(angle: f64, center: &Point2d) -> Self {
+        Self::translation(-center.x, -center.y)
+            .then(&Self::rotation(angle))
+            .then(&Self::translation(center.x, center.y))
+    }
+
+    pub fn scale(sx: f64, sy: f64) -> Self {
+        Self {
+            a: sx,
+            b: 0.0,
+            c: 0.0,
+            d: 0.0,
+            e: sy,
+            f: 0.0,
+        }
+    }
+
+    /// x' = x + kx * y and y' = ky * x + y
+    pub fn shear(kx: f64, ky: f64) -> Self {
+        Self {
+            b: kx,
+            d: ky,
+            ..Self::identity()
+        }
+    }
+
+    /// The composition that applies `self` first and `next` afterwards
+    pub fn then(&self, next: &Affine2) -> Affine2 {
+        Affine2 {
+            a: next.a * self.a + next.b * self.d,
+            b: next.a * self.b + next.b * self.e,
+            c: next.a * self.c + next.b * self.f + next.c,
+            d: next.d * self.a + next.e * self.d,
+            e: next.d * self.b + next.e * self.e,
+            f: next.d * self.c + next.e * self.f + next.f,
+        }
+    }
+
+    pub fn determinant(&self) -> f64 {
+        self.a * self.e - self.b * self.d
+    }
+
+    /// `None` when the map collapses the plane onto a line or a point
+    pub fn inverse(&self) -> Option<Affine2> {
+        let determinant = self.determinant();
+        if determinant == 0.0 {
+            return None;
+        }
+
+        let a = self.e / determinant;
+        let b = -self.b / determinant;
+        let d = -self.d / determinant;
+        let e = self.a / determinant;
+        Some(Affine2 {
+            a,
+            b,
+            c: -(a * self.c + b * self.f),
+            d,
+            e,
+            f: -(d * self.c + e * self.f),
+        })
+    }
+
+    pub fn apply(&self, point: &Point2d) -> Point2d {
+        Point2d::new(
+            self.a * point.x + self.b * point.y + self.c,
+            self.d * point.x + self.e * point.y + self.f,
+        )
+    }
+}
+
+/// Geometry that can be moved around by an [`Affine2`]
+pub trait Transform {
+    fn transform(&self, affine: &Affine2) -> Self;
+}
+
+impl Transform for Point2d {
+    fn transform(&self, affine: &Affine2) -> Self {
+        affine.apply(self)
+    }
+}
+
+impl Transform for Segment {
+    fn transform(&self, affine: &Affine2) -> Self {
+        Segment::new(affine.apply(&self.start), affine.apply(&self.end))
+    }
+}
+
+impl Transform for Polygon {
+    /// Mirroring maps (negative determinant) would flip the winding, so the points are reversed
+    /// to keep it the way [`Polygon`] expects
+    fn transform(&self, affine: &Affine2) -> Self {
+        let mut points = self
+            .iter_points()
+            .map(|point| affine.apply(point))
+            .collect::<Vec<_>>();
+        if affine.determinant() < 0.0 {
+            points.reverse();
+        }
+        Polygon::from_points(points)
+    }
+}
+
+impl Transform for Bounds {
+    /// The bounds of the transformed box, which is larger than the box itself unless the map only
+    /// scales and translates
+    fn transform(&self, affine: &Affine2) -> Self {
+        let corners = [
+            Point2d::new(self.min_x, self.min_y),
+            Point2d::new(self.min_x, self.max_y),
+            Point2d::new(self.max_x, self.max_y),
+            Point2d::new(self.max_x, self.min_y),
+        ]
+        .map(|corner| affine.apply(&corner));
+        Bounds::from_points(&corners)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        affine::{Affine2, Transform},
+        point::Point2d,
+        polygon::Polygon,
+    };
+    use std::f64::consts::FRAC_PI_2;
+
+    #[test]
+    fn test_composition_and_inverse() {
+        let point = Point2d::new(1.0, 2.0);
+
+        let affine = Affine2::scale(2.0, 3.0)
+            .then(&Affine2::rotation(FRAC_PI_2))
+            .then(&Affine2::translation(10.0, 0.0));
+        let actual = point.transform(&affine);
+        // (2, 6) rotated a quarter turn is (-6, 2)
+        assert!(actual.distance_to(&Point2d::new(4.0, 2.0)) < 1e-12);
+
+        let inverse = affine.inverse().unwrap();
+        assert!(actual.transform(&inverse).distance_to(&point) < 1e-12);
+
+        let sheared = point.transform(&Affine2::shear(1.0, 0.0));
+        assert_eq!(sheared, Point2d::new(3.0, 2.0));
+
+        assert!(Affine2::scale(1.0, 0.0).inverse().is_none());
+    }
+
+    #[test]
+    fn test_mirroring_a_polygon_keeps_its_winding() {
+        let p0 = Point2d::new(1.0, 1.0);
+        let p1 = Point2d::new(1.0, 3.0);
+        let p2 = Point2d::new(3.0, 3.0);
+        let p3 = Point2d::new(3.0, 1.0);
+        let points = vec![p0, p1, p2, p3];
+        let square = Polygon::from_points(points);
+
+        let mirrored = square.transform(&Affine2::scale(-1.0, 1.0));
+
+        assert_eq!(mirrored.signed_area(), square.signed_area());
+        assert_eq!(mirrored.bounds.min_x, -3.0);
+        assert_eq!(mirrored.bounds.max_x, -1.0);
+
+        let bounds = square.bounds.transform(&Affine2::rotation_around(
+            FRAC_PI_2 / 2.0,
+            &Point2d::new(2.0, 2.0),
+        ));
+        let half_diagonal = 2f64.sqrt();
+        assert!((bounds.max_x - bounds.min_x - 2.0 * half_diagonal).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_then_applies_self_first() {
+        let point = Point2d::new(1.0, 1.0);
+        let shift = Affine2::translation(1.0, 0.0);
+        let double = Affine2::scale(2.0, 2.0);
+
+        assert_eq!(
+            point.transform(&shift.then(&double)),
+            Point2d::new(4.0, 2.0)
+        );
+        assert_eq!(
+            point.transform(&double.then(&shift)),
+            Point2d::new(3.0, 2.0)
+        );
+        assert_eq!(shift.then(&Affine2::identity()), shift);
+        assert_eq!(Affine2::identity().then(&shift), shift);
+    }
+
+    #[test]
+    fn test_inverse_round_trip() {
+        let affine = Affine2::shear(0.5, -0.25)
+            .then(&Affine2::rotation(0.3))
+            .then(&Affine2::scale(2.0, -3.0))
+            .then(&Affine2::translation(-4.0, 7.0));
+        let inverse = affine.inverse().unwrap();
+
+        let point = Point2d::new(-1.5, 2.5);
+        assert!(
+            point
+                .transform(&affine)
+                .transform(&inverse)
+                .distance_to(&point)
+                < 1e-12
+        );
+        assert!(
+            point
+                .transform(&inverse)
+                .transform(&affine)
+                .distance_to(&point)
+                < 1e-12
+        );
+        assert!((affine.determinant() * inverse.determinant() - 1.0).abs() < 1e-12);
+
+        // every point lands on the line y = 2 * x, which can't be undone
+        let onto_a_line = Affine2 {
+            a: 1.0,
+            b: 2.0,
+            c: 1.0,
+            d: 2.0,
+            e: 4.0,
+            f: 2.0,
+        };
+        assert_eq!(onto_a_line.determinant(), 0.0);
+        assert!(onto_a_line.inverse().is_none());
+        assert!(Affine2::scale(0.0, 0.0).inverse().is_none());
+    }
+
+    #[test]
+    fn test_shear() {
+        let point = Point2d::new(2.0, 4.0);
+
+        assert_eq!(
+            point.transform(&Affine2::shear(0.5, 0.0)),
+            Point2d::new(4.0, 4.0)
+        );
+        assert_eq!(
+            point.transform(&Affine2::shear(0.0, 2.0)),
+            Point2d::new(2.0, 8.0)
+        );
+        assert_eq!(
+            point.transform(&Affine2::shear(0.5, 2.0)),
+            Point2d::new(4.0, 8.0)
+        );
+
+        // shearing along one axis keeps the area
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 2.0);
+        let p2 = Point2d::new(3.0, 2.0);
+        let p3 = Point2d::new(3.0, 0.0);
+        let rectangle = Polygon::from_points(vec![p0, p1, p2, p3]);
+        let sheared = rectangle.transform(&Affine2::shear(1.5, 0.0));
+        assert_eq!(sheared.signed_area(), rectangle.signed_area());
+    }
+
+    #[test]
+    fn test_rotation_around_keeps_the_center() {
+        let center = Point2d::new(2.0, 2.0);
+        let quarter_turn = Affine2::rotation_around(FRAC_PI_2, &center);
+
+        assert!(center.transform(&quarter_turn).distance_to(&center) < 1e-12);
+        let turned = Point2d::new(3.0, 2.0).transform(&quarter_turn);
+        assert!(turned.distance_to(&Point2d::new(2.0, 3.0)) < 1e-12);
+        let turned = Point2d::new(2.0, 3.0).transform(&quarter_turn);
+        assert!(turned.distance_to(&Point2d::new(1.0, 2.0)) < 1e-12);
+    }
+
+    #[test]
+    fn test_reflections_keep_the_area_positive() {
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(1.0, 3.0);
+        let p2 = Point2d::new(4.0, 1.0);
+        let triangle = Polygon::from_points(vec![p0, p1, p2]);
+        assert!(triangle.signed_area() > 0.0);
+
+        let across_the_diagonal = Affine2 {
+            a: 0.0,
+            b: 1.0,
+            c: 0.0,
+            d: 1.0,
+            e: 0.0,
+            f: 0.0,
+        };
+        let upside_down = Affine2::scale(1.0, -1.0).then(&Affine2::translation(0.0, 5.0));
+        let mirrored_and_turned = Affine2::scale(-2.0, 1.0).then(&Affine2::rotation(1.0));
+        for reflection in [across_the_diagonal, upside_down, mirrored_and_turned] {
+            assert!(reflection.determinant() < 0.0);
+            let reflected = triangle.transform(&reflection);
+            let expected = triangle.signed_area() * reflection.determinant().abs();
+            assert!(reflected.signed_area() > 0.0);
+            assert!((reflected.signed_area() - expected).abs() < 1e-12);
+        }
+    }
+}
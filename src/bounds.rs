@@ -20,6 +20,24 @@ impl Bounds {
             max_y: *max(&ys),
         }
     }
+
+    /// The smallest box containing both
+    pub fn union(&self, other: &Bounds) -> Bounds {
+        Bounds {
+            min_x: self.min_x.min(other.min_x),
+            max_x: self.max_x.max(other.max_x),
+            min_y: self.min_y.min(other.min_y),
+            max_y: self.max_y.max(other.max_y),
+        }
+    }
+
+    /// Whether the two boxes are no further than `distance` apart
+    pub fn is_within(&self, other: &Bounds, distance: f64) -> bool {
+        self.min_x - distance <= other.max_x
+            && other.min_x - distance <= self.max_x
+            && self.min_y - distance <= other.max_y
+            && other.min_y - distance <= self.max_y
+    }
 }
 
 fn min(values: &[f64]) -> &f64 {
@@ -2,7 +2,8 @@ use std::sync::mpsc;
 use thread_pool::ThreadPool;
 
 use crate::{
-    polygon::{polygons_from_unordered_segments, Polygon},
+    point::Point2d,
+    polygon::{polygons_from_unordered_segments, turn, Polygon},
     segment::Segment,
     vector::Vector2d,
 };
@@ -51,8 +52,9 @@ pub fn clip_one_another(segments_a: &[Segment], segments_b: &[Segment]) -> Vec<S
     // each segment can cross a polygon at most twice and there are 2 polygons so 2 * 2
     let mut clipped_segments = Vec::with_capacity(2 * 2 * upper_bound);
 
-    clip(segments_b, segments_a, &mut clipped_segments);
-    clip(segments_a, segments_b, &mut clipped_segments);
+    // edges both polygons share are kept once, from the first of them
+    clip(segments_b, segments_a, true, &mut clipped_segments);
+    clip(segments_a, segments_b, false, &mut clipped_segments);
 
     clipped_segments
 }
@@ -60,85 +62,214 @@ pub fn clip_one_another(segments_a: &[Segment], segments_b: &[Segment]) -> Vec<S
 fn clip(
     mould_segments: &[Segment],
     polygon_segments: &[Segment],
+    keeps_shared: bool,
     clipped_segments: &mut Vec<Segment>,
 ) {
-    println!(
-        "=== MOULD === {} POLYGON {}",
-        mould_segments[0], polygon_segments[0]
-    );
     for segment in polygon_segments {
-        clip_segment(mould_segments, segment, polygon_segments, clipped_segments);
+        clip_segment(mould_segments, segment, keeps_shared, clipped_segments);
     }
 }
 
-// TODO: plenty of room for improvement: compute intersections only once
+// Cuts the segment wherever it meets the mould and keeps the pieces outside of it, joined back
+// together when nothing happens between them. A piece running along an edge of the mould is
+// between both polygons if they are on either side of it, and kept only once otherwise.
 fn clip_segment(
     mould_segments: &[Segment],
     segment: &Segment,
-    polygon_segments: &[Segment],
+    keeps_shared: bool,
     clipped_segments: &mut Vec<Segment>,
 ) {
-    if segment.is_inside_of_or_touches(mould_segments) {
-        println!("segment {segment} is inside of or touches mould, skipping it");
-        return;
-    }
+    let along: Vector2d = segment.into();
+    let mut cuts = mould_segments
+        .iter()
+        .flat_map(|mould_segment| meeting_points(segment, mould_segment))
+        .map(|point| {
+            let t = Vector2d::from_points(&segment.start, &point).dot(&along) / along.norm_sq();
+            (t, point)
+        })
+        .filter(|(t, _)| *t > 0.0 && *t < 1.0)
+        .collect::<Vec<_>>();
+    cuts.sort_by(|a, b| a.0.total_cmp(&b.0));
+    cuts.dedup_by(|a, b| a.1 == b.1);
 
-    let clipped_segments_before = clipped_segments.len();
+    let mut points = Vec::with_capacity(cuts.len() + 2);
+    points.push(segment.start.clone());
+    points.extend(cuts.into_iter().map(|(_, point)| point));
+    points.push(segment.end.clone());
+
+    let mut kept_from = None;
+    for i in 0..points.len() - 1 {
+        let piece = Segment::new(points[i].clone(), points[i + 1].clone());
+        let is_kept = match mould_segments
+            .iter()
+            .find(|mould_segment| runs_along(&piece, mould_segment))
+        {
+            Some(mould_segment) => {
+                let mould_along: Vector2d = mould_segment.into();
+                let piece_along: Vector2d = (&piece).into();
+                keeps_shared && mould_along.dot(&piece_along) > 0.0
+            }
+            None => !covers(mould_segments, &piece.at(0.5)),
+        };
 
-    for mould_segment in mould_segments {
-        if mould_segment.is_inside_of_or_touches(polygon_segments) {
-            let vector: Vector2d = segment.into();
-            let mould_vector: Vector2d = mould_segment.into();
-            let is_outside = mould_vector.dot(&vector) == -1.0;
-            let process = mould_segment.is_collinear_with(segment) && is_outside;
-            if !process {
-                continue;
+        match (is_kept, kept_from) {
+            (true, None) => kept_from = Some(i),
+            (false, Some(from)) => {
+                clipped_segments.push(Segment::new(points[from].clone(), points[i].clone()));
+                kept_from = None;
             }
+            _ => {}
         }
+    }
+    if let Some(from) = kept_from {
+        let last = points.len() - 1;
+        let kept = if from == 0 {
+            segment.clone()
+        } else {
+            Segment::new(points[from].clone(), points[last].clone())
+        };
+        clipped_segments.push(kept);
+    }
+}
 
-        let intersections = mould_segment.intersections_with(segment);
-        for intersection in intersections {
-            let sub_segment_a = Segment::new(intersection.clone(), segment.start.clone());
-            let sub_segment_b = Segment::new(intersection.clone(), segment.end.clone());
+// the ends of either segment lying on the other one, or the point where they cross, computed the
+// same way whichever segment comes first so that both get cut at exactly the same point
+fn meeting_points(segment: &Segment, other: &Segment) -> Vec<Point2d> {
+    let mut points = Vec::with_capacity(2);
+    if !segment.bounds().is_within(other.bounds(), 0.0) {
+        return points;
+    }
 
-            if sub_segment_a.is_point() {
-                println!("a is a point so don't push anything (the undivided segment will be pushed at loop end): {}", sub_segment_a);
-                continue;
-            }
+    let d1 = turn(&segment.start, &segment.end, &other.start);
+    let d2 = turn(&segment.start, &segment.end, &other.end);
+    let d3 = turn(&other.start, &other.end, &segment.start);
+    let d4 = turn(&other.start, &other.end, &segment.end);
+    if d1 * d2 < 0.0 && d3 * d4 < 0.0 {
+        let (first, second) = if segment_key(segment) <= segment_key(other) {
+            (segment, other)
+        } else {
+            (other, segment)
+        };
+        let (start, end) = ends_in_order(first);
+        let (other_start, other_end) = ends_in_order(second);
+        let along = Vector2d::from_points(start, end);
+        let other_along = Vector2d::from_points(other_start, other_end);
+        let t = Vector2d::from_points(start, other_start).cross(&other_along)
+            / along.cross(&other_along);
+        points.push(Point2d::new(start.x + t * along.x, start.y + t * along.y));
+        return points;
+    }
 
-            if sub_segment_b.is_point() {
-                println!("b is a point so don't push anything (the undivided segment will be pushed at loop end): {}", sub_segment_b);
-                continue;
-            }
+    if d1 == 0.0 && segment.boxes(&other.start) {
+        points.push(other.start.clone());
+    }
+    if d2 == 0.0 && segment.boxes(&other.end) {
+        points.push(other.end.clone());
+    }
+    if d3 == 0.0 && other.boxes(&segment.start) {
+        points.push(segment.start.clone());
+    }
+    if d4 == 0.0 && other.boxes(&segment.end) {
+        points.push(segment.end.clone());
+    }
+    points
+}
+
+fn ends_in_order(segment: &Segment) -> (&Point2d, &Point2d) {
+    if segment.start.key <= segment.end.key {
+        (&segment.start, &segment.end)
+    } else {
+        (&segment.end, &segment.start)
+    }
+}
+
+fn segment_key(segment: &Segment) -> ((u64, u64), (u64, u64)) {
+    let (start, end) = ends_in_order(segment);
+    (start.key, end.key)
+}
 
-            // println!("a: {sub_segment_a} b: {sub_segment_b} mould: {mould_segment}");
-            let kept_sub_segment = if sub_segment_a.points_inwards_of(mould_segment) {
-                sub_segment_b
-            } else {
-                // sub_segment_a_with_correct_orientation
-                Segment::new(segment.start.clone(), intersection.clone())
-            };
-
-            println!(
-                "intersection between mould {} and {} pushing segment {}",
-                mould_segment, segment, kept_sub_segment
-            );
-            clipped_segments.push(kept_sub_segment);
+// whether the piece lies on the mould segment
+fn runs_along(piece: &Segment, mould_segment: &Segment) -> bool {
+    let is_on = |point: &Point2d| {
+        turn(&mould_segment.start, &mould_segment.end, point) == 0.0 && mould_segment.boxes(point)
+    };
+    is_on(&piece.start) && is_on(&piece.end)
+}
+
+// non-zero winding test of a point away from the edges, which may make up several rings
+fn covers(mould_segments: &[Segment], point: &Point2d) -> bool {
+    let mut winding = 0;
+    for segment in mould_segments {
+        let (a, b) = (&segment.start, &segment.end);
+        let straddles = (a.y > point.y) != (b.y > point.y);
+        if straddles {
+            let x = a.x + (point.y - a.y) * (b.x - a.x) / (b.y - a.y);
+            if point.x < x {
+                winding += if b.y < a.y { 1 } else { -1 };
+            }
         }
     }
+    winding != 0
+}
 
-    let clipped_segments_after = clipped_segments.len();
-    let segment_went_unclipped = clipped_segments_after == clipped_segments_before;
+/// The part of `subject` inside of the convex `mould` (Sutherland-Hodgman), or `None` when they
+/// don't overlap. `subject` may be concave or a hole, whose winding is kept.
+///
+/// A concave `subject` that the mould cuts into several pieces still comes out as a single ring:
+/// the pieces are joined by bridges of zero width running along the edges of the mould. The area
+/// is right, but the ring touches itself along the bridges.
+pub fn intersect_convex(subject: &Polygon, mould: &Polygon) -> Option<Polygon> {
+    let mut points = subject.points.clone();
+    for edge in mould.segments.iter() {
+        let n = points.len();
+        if n == 0 {
+            break;
+        }
+
+        let height = |point: &Point2d| turn(&edge.start, &edge.end, point);
+        let mut clipped = Vec::with_capacity(n + 1);
+        for i in 0..n {
+            let current = &points[i];
+            let next = &points[(i + 1) % n];
+            let current_height = height(current);
+            let next_height = height(next);
+
+            if current_height >= 0.0 {
+                clipped.push(current.clone());
+            }
+            let crosses = (current_height < 0.0 && next_height > 0.0)
+                || (current_height > 0.0 && next_height < 0.0);
+            if crosses {
+                let t = current_height / (current_height - next_height);
+                let x = current.x + t * (next.x - current.x);
+                let y = current.y + t * (next.y - current.y);
+                clipped.push(Point2d::new(x, y));
+            }
+        }
+        points = clipped;
+    }
 
-    if segment_went_unclipped {
-        println!("there is no intersection, just pushing {}", segment);
-        clipped_segments.push(segment.clone());
+    if points.len() < 3 {
+        return None;
+    }
+    let intersection = Polygon::from_points(points);
+    if intersection.signed_area() == 0.0 {
+        return None;
     }
+    Some(intersection)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{point::Point2d, vector::Vector2d};
+    use std::f64::consts::PI;
+
+    use crate::{
+        clip,
+        overlay::{self, Region},
+        point::Point2d,
+        polygon::Polygon,
+        vector::Vector2d,
+    };
 
     #[test]
     fn test() {
@@ -151,4 +282,147 @@ mod tests {
         let b = Vector2d::from_points(&start_b, &end_b);
         assert!(a.dot(&b) < 0.0);
     }
+
+    fn square(x: f64, y: f64, size: f64) -> Polygon {
+        let p0 = Point2d::new(x, y);
+        let p1 = Point2d::new(x, y + size);
+        let p2 = Point2d::new(x + size, y + size);
+        let p3 = Point2d::new(x + size, y);
+        Polygon::from_points(vec![p0, p1, p2, p3])
+    }
+
+    fn summed_area(polygons: Vec<Polygon>) -> f64 {
+        clip::sum(polygons)
+            .iter()
+            .map(Polygon::signed_area)
+            .sum::<f64>()
+    }
+
+    #[test]
+    fn test_sum_of_outlines_with_many_vertices() {
+        let triangle = Polygon::from_points(vec![
+            Point2d::new(0.0, 0.0),
+            Point2d::new(1.0, 2.0),
+            Point2d::new(2.0, 0.0),
+        ]);
+        let crossing = square(0.5, 0.5, 1.0);
+        let expected = triangle.signed_area() + crossing.signed_area() - 0.875;
+        let actual = summed_area(vec![triangle, crossing]);
+        assert!((actual - expected).abs() < 1e-12, "{actual} {expected}");
+
+        // no crossing is computed exactly, but both outlines get cut at the same points
+        let circle = |x: f64, radius: f64| {
+            let points = (0..100)
+                .map(|i| {
+                    let angle = -(i as f64) * PI / 50.0;
+                    Point2d::new(x + radius * angle.cos(), radius * angle.sin())
+                })
+                .collect();
+            Polygon::from_points(points)
+        };
+        let circles = vec![circle(0.0, 1.0), circle(1.0, 1.0)];
+        let expected = overlay::coverage_depth(&circles)
+            .iter()
+            .map(Region::area)
+            .sum::<f64>();
+        let lens = summed_area(circles);
+        assert!((lens - expected).abs() < 1e-9, "{lens} {expected}");
+    }
+
+    #[test]
+    fn test_sum_of_segments_crossing_several_times() {
+        // three teeth standing on a base, all of them crossing a band lying across
+        let points = [
+            (0.0, -1.0),
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (1.0, 3.0),
+            (2.0, 3.0),
+            (2.0, 0.0),
+            (4.0, 0.0),
+            (4.0, 3.0),
+            (5.0, 3.0),
+            (5.0, 0.0),
+            (7.0, 0.0),
+            (7.0, 3.0),
+            (8.0, 3.0),
+            (8.0, 0.0),
+            (9.0, 0.0),
+            (9.0, -1.0),
+        ];
+        let comb = Polygon::from_points(points.map(|(x, y)| Point2d::new(x, y)).to_vec());
+        let band = Polygon::from_points(vec![
+            Point2d::new(0.0, 1.0),
+            Point2d::new(0.0, 2.0),
+            Point2d::new(10.0, 2.0),
+            Point2d::new(10.0, 1.0),
+        ]);
+        assert_eq!(comb.signed_area(), 18.0);
+
+        let sum = clip::sum(vec![comb, band]);
+
+        // each side of the band is cut six times, and the gaps between the teeth become holes
+        let area = sum.iter().map(Polygon::signed_area).sum::<f64>();
+        assert_eq!(area, 18.0 + 10.0 - 3.0);
+        let holes = sum.iter().filter(|ring| ring.signed_area() < 0.0).count();
+        assert_eq!(holes, 2);
+    }
+
+    #[test]
+    fn test_sum_of_shared_collinear_edges() {
+        // touching along a whole edge
+        let touching = clip::sum(vec![square(0.0, 0.0, 1.0), square(1.0, 0.0, 1.0)]);
+        assert_eq!(touching.len(), 1);
+        assert_eq!(touching[0].signed_area(), 2.0);
+
+        // touching along part of an edge of each
+        let sliding = clip::sum(vec![square(0.0, 0.0, 2.0), square(2.0, 1.0, 2.0)]);
+        assert_eq!(sliding.len(), 1);
+        assert_eq!(sliding[0].signed_area(), 8.0);
+
+        // in a corner, sharing parts of two edges running the same way
+        let cornered = clip::sum(vec![square(0.0, 0.0, 2.0), square(0.0, 0.0, 1.0)]);
+        assert_eq!(cornered.len(), 1);
+        assert_eq!(cornered[0].signed_area(), 4.0);
+
+        let twice = clip::sum(vec![square(0.0, 0.0, 1.0), square(0.0, 0.0, 1.0)]);
+        assert_eq!(twice, vec![square(0.0, 0.0, 1.0)]);
+    }
+
+    #[test]
+    fn test_intersect_convex() {
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 2.0);
+        let p2 = Point2d::new(2.0, 2.0);
+        let p3 = Point2d::new(2.0, 0.0);
+        let mould = Polygon::from_points(vec![p0, p1, p2, p3]);
+
+        // an L shape sticking out of the square on two sides
+        let p0 = Point2d::new(1.0, 1.0);
+        let p1 = Point2d::new(1.0, 3.0);
+        let p2 = Point2d::new(2.0, 3.0);
+        let p3 = Point2d::new(2.0, 2.0);
+        let p4 = Point2d::new(3.0, 2.0);
+        let p5 = Point2d::new(3.0, 1.0);
+        let subject = Polygon::from_points(vec![p0, p1, p2, p3, p4, p5]);
+
+        let intersection = clip::intersect_convex(&subject, &mould).unwrap();
+        assert_eq!(intersection.signed_area(), 1.0);
+        assert_eq!(intersection.bounds.max_x, 2.0);
+        assert_eq!(intersection.bounds.max_y, 2.0);
+
+        let mut points = subject.points.clone();
+        points.reverse();
+        let hole = Polygon::from_points(points);
+        let intersection = clip::intersect_convex(&hole, &mould).unwrap();
+        assert_eq!(intersection.signed_area(), -1.0);
+
+        let far = Polygon::from_points(
+            subject
+                .iter_points()
+                .map(|point| Point2d::new(point.x + 10.0, point.y))
+                .collect(),
+        );
+        assert!(clip::intersect_convex(&far, &mould).is_none());
+    }
 }
@@ -0,0 +1,249 @@
+use crate::point::Point2d;
+use crate::polygon::{turn, Polygon};
+use crate::triangulate::Triangulation;
+
+/// Splits `polygon` minus `holes` into convex polygons using Hertel-Mehlhorn: triangulate, then
+/// greedily remove every diagonal whose removal leaves a convex piece behind.
+///
+/// The result has at most four times as many pieces as an optimal decomposition and every piece
+/// is wound the way [`Polygon`] expects. Holes may be given in either winding.
+pub fn decompose(polygon: &Polygon, holes: &[Polygon]) -> Vec<Polygon> {
+    if holes.is_empty() && polygon.is_convex() {
+        return vec![polygon.clone()];
+    }
+
+    let Triangulation { points, triangles } = Triangulation::ear_clipping(polygon, holes);
+    let mut pieces = triangles
+        .into_iter()
+        .map(|triangle| triangle.to_vec())
+        .collect::<Vec<_>>();
+
+    // sweep until nothing merges: a merge changes a piece that earlier ones were already
+    // compared against, so a diagonal that couldn't go before may be removable now
+    let mut merged_any = true;
+    while merged_any {
+        merged_any = false;
+        let mut i = 0;
+        while i < pieces.len() {
+            let mut j = i + 1;
+            while j < pieces.len() {
+                match merge(&points, &pieces[i], &pieces[j]) {
+                    Some(merged) => {
+                        pieces[i] = merged;
+                        pieces.swap_remove(j);
+                        merged_any = true;
+                    }
+                    None => j += 1,
+                }
+            }
+            i += 1;
+        }
+    }
+
+    pieces
+        .into_iter()
+        .map(|piece| {
+            let piece_points = piece.into_iter().map(|i| points[i].clone()).collect();
+            Polygon::from_points(piece_points)
+        })
+        .collect()
+}
+
+/// Smallest convex polygon containing every point (Andrew's monotone chain), wound the way
+/// [`Polygon`] expects and without collinear vertices. `None` when the points are all on a line.
+pub fn hull(points: &[Point2d]) -> Option<Polygon> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    sorted.dedup();
+
+    // both chains go counter-clockwise when y points up, i.e. against the winding of Polygon
+    let chain = |points: &mut dyn Iterator<Item = &Point2d>| {
+        let mut chain: Vec<Point2d> = Vec::new();
+        for point in points {
+            while chain.len() >= 2
+                && turn(&chain[chain.len() - 2], &chain[chain.len() - 1], point) >= 0.0
+            {
+                chain.pop();
+            }
+            chain.push(point.clone());
+        }
+        chain.pop();
+        chain
+    };
+    let mut hull = chain(&mut sorted.iter());
+    hull.extend(chain(&mut sorted.iter().rev()));
+
+    if hull.len() < 3 {
+        return None;
+    }
+    hull.reverse();
+    Some(Polygon::from_points(hull))
+}
+
+// joins two pieces along the diagonal they share, if any, as long as the result stays convex
+fn merge(points: &[Point2d], a: &[usize], b: &[usize]) -> Option<Vec<usize>> {
+    let n = a.len();
+    let m = b.len();
+    for i in 0..n {
+        let start = a[i];
+        let end = a[(i + 1) % n];
+        let shared = (0..m).find(|&j| b[j] == end && b[(j + 1) % m] == start);
+        let Some(j) = shared else {
+            continue;
+        };
+
+        // walk a from end all the way around to start, then b from start to end without
+        // repeating either of them
+        let mut merged = Vec::with_capacity(n + m - 2);
+        merged.extend((1..=n).map(|k| a[(i + k) % n]));
+        merged.extend((2..m).map(|k| b[(j + k) % m]));
+
+        let is_convex = (0..merged.len()).all(|k| {
+            let len = merged.len();
+            let prev = &points[merged[(k + len - 1) % len]];
+            let vertex = &points[merged[k]];
+            let next = &points[merged[(k + 1) % len]];
+            turn(prev, vertex, next) >= 0.0
+        });
+        return is_convex.then_some(merged);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{clip, convex, point::Point2d, polygon::Polygon};
+    use float_cmp::ApproxEq;
+
+    #[test]
+    fn test_hull() {
+        let points = [
+            Point2d::new(0.0, 0.0),
+            Point2d::new(1.0, 1.0),
+            Point2d::new(2.0, 0.0),
+            Point2d::new(1.0, 0.0),
+            Point2d::new(2.0, 2.0),
+            Point2d::new(0.0, 2.0),
+            Point2d::new(0.5, 1.5),
+            Point2d::new(0.0, 2.0),
+        ];
+
+        let hull = convex::hull(&points).unwrap();
+
+        assert_eq!(hull.points.len(), 4);
+        assert_eq!(hull.signed_area(), 4.0);
+        assert!(hull.is_convex());
+
+        let collinear = [
+            Point2d::new(0.0, 0.0),
+            Point2d::new(1.0, 1.0),
+            Point2d::new(2.0, 2.0),
+        ];
+        assert!(convex::hull(&collinear).is_none());
+    }
+
+    #[test]
+    fn test_decompose_l_shape_into_two_convex_pieces() {
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 2.0);
+        let p2 = Point2d::new(1.0, 2.0);
+        let p3 = Point2d::new(1.0, 1.0);
+        let p4 = Point2d::new(2.0, 1.0);
+        let p5 = Point2d::new(2.0, 0.0);
+        let points = vec![p0, p1, p2, p3, p4, p5];
+        let l_shape = Polygon::from_points(points);
+
+        let pieces = convex::decompose(&l_shape, &[]);
+
+        assert_eq!(pieces.len(), 2);
+        assert!(pieces.iter().all(|piece| piece.is_convex()));
+        let area = pieces.iter().map(|piece| piece.signed_area()).sum::<f64>();
+        assert_eq!(area, 3.0);
+    }
+
+    #[test]
+    fn test_decompose_square_with_a_hole() {
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 4.0);
+        let p2 = Point2d::new(4.0, 4.0);
+        let p3 = Point2d::new(4.0, 0.0);
+        let points = vec![p0, p1, p2, p3];
+        let square = Polygon::from_points(points);
+
+        let p0 = Point2d::new(1.0, 1.0);
+        let p1 = Point2d::new(2.0, 1.0);
+        let p2 = Point2d::new(2.0, 2.0);
+        let p3 = Point2d::new(1.0, 2.0);
+        let points = vec![p0, p1, p2, p3];
+        let hole = Polygon::from_points(points);
+
+        let pieces = convex::decompose(&square, std::slice::from_ref(&hole));
+        let mut hole_points = hole.points;
+        hole_points.reverse();
+        let hole = Polygon::from_points(hole_points);
+
+        assert!(pieces.len() > 1);
+        assert!(pieces.iter().all(|piece| piece.is_convex()));
+        let area = pieces.iter().map(|piece| piece.signed_area()).sum::<f64>();
+        assert!(area.approx_eq(15.0, (1e-12, 1)));
+        for (i, piece) in pieces.iter().enumerate() {
+            for other in pieces[i + 1..].iter() {
+                let overlap = clip::intersect_convex(piece, other);
+                assert!(overlap.is_none_or(|overlap| overlap.signed_area().abs() < 1e-12));
+            }
+            let in_hole = clip::intersect_convex(piece, &hole);
+            assert!(in_hole.is_none_or(|in_hole| in_hole.signed_area().abs() < 1e-12));
+        }
+    }
+
+    #[test]
+    fn test_decompose_leaves_no_removable_diagonal() {
+        for seed in 1..200_u64 {
+            // a star of 12 to 27 spikes, each vertex at a pseudo-random distance from the centre
+            let mut state = seed;
+            let mut next = || {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (state >> 33) as f64 / (1_u64 << 31) as f64
+            };
+            let n = 12 + (next() * 16.0) as usize;
+            let points = (0..n)
+                .map(|k| {
+                    let angle = -std::f64::consts::TAU * k as f64 / n as f64;
+                    let radius = 0.2 + next();
+                    Point2d::new(radius * angle.cos(), radius * angle.sin())
+                })
+                .collect();
+            let star = Polygon::from_points(points);
+
+            let pieces = convex::decompose(&star, &[]);
+
+            let area = pieces.iter().map(|piece| piece.signed_area()).sum::<f64>();
+            assert!(area.approx_eq(star.signed_area(), (1e-12, 4)));
+            let mut all_points: Vec<Point2d> = Vec::new();
+            let indices = pieces
+                .iter()
+                .map(|piece| {
+                    piece
+                        .points
+                        .iter()
+                        .map(|point| match all_points.iter().position(|p| p == point) {
+                            Some(index) => index,
+                            None => {
+                                all_points.push(point.clone());
+                                all_points.len() - 1
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            for (i, piece) in indices.iter().enumerate() {
+                assert!(pieces[i].is_convex());
+                for other in indices[i + 1..].iter() {
+                    assert!(convex::merge(&all_points, piece, other).is_none());
+                }
+            }
+        }
+    }
+}
@@ -1,28 +1,385 @@
+use crate::bounds::Bounds;
+use crate::point::Point2d;
 use crate::polygon::Polygon;
-use rustvision::{image::Image, rgb, save_pnm_p6, shapes::Polygon as RustVisionPolygon, vec2};
+use crate::raster::{FillRule, Grid};
+use rustvision::{
+    color::Color,
+    image::Image,
+    rgb, save_pnm_p6,
+    shapes::{Line, Polygon as RustVisionPolygon},
+    vec::Vec2d,
+    vec2,
+};
 
-impl From<&Polygon> for RustVisionPolygon {
-    fn from(polygon: &Polygon) -> Self {
-        // TODO: drop the need for scaling
-        let y_offset = 2.0;
-        let scale = 100.0;
+// empty pixels kept around the drawing on every side
+const MARGIN: f64 = 20.0;
+// in pixels
+const DOT_RADIUS: i64 = 2;
+const ARROW_SIZE: f64 = 8.0;
+const GLYPH_SCALE: i64 = 2;
+
+// digits 3 pixels wide and 5 high, a row per byte with the leftmost pixel as the highest bit
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/// How a [`Layer`] is drawn, the markers take the stroke colour, or the fill one without a stroke
+#[derive(Clone, Debug)]
+pub struct Style {
+    pub stroke: Option<Color>,
+    pub fill: Option<Color>,
+    /// A dot on every vertex
+    pub vertices: bool,
+    /// An arrow halfway along every segment, pointing from its start to its end
+    pub arrows: bool,
+    /// The index of every vertex next to it
+    pub labels: bool,
+}
+
+impl Default for Style {
+    /// White outlines only
+    fn default() -> Self {
+        Self {
+            stroke: Some(rgb!(255, 255, 255)),
+            fill: None,
+            vertices: false,
+            arrows: false,
+            labels: false,
+        }
+    }
+}
+
+/// Polygons drawn the same way. They are filled together by [`FillRule::NonZero`], so holes wound
+/// the other way around stay empty.
+#[derive(Clone, Debug)]
+pub struct Layer {
+    pub polygons: Vec<Polygon>,
+    pub style: Style,
+}
+
+/// Maps polygon coordinates to pixels: the bounds of what is drawn, scaled the same along both
+/// axes to fit within the image and its margins, centered. Pixel rows go down the y axis as
+/// documented on [`Polygon`].
+#[derive(Clone, Debug)]
+pub struct Viewport {
+    pub bounds: Bounds,
+    pub scale: f64,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Viewport {
+    pub fn fit(bounds: &Bounds, width: usize, height: usize) -> Self {
+        let available_width = (width as f64 - 2.0 * MARGIN).max(1.0);
+        let available_height = (height as f64 - 2.0 * MARGIN).max(1.0);
+        let span_x = bounds.max_x - bounds.min_x;
+        let span_y = bounds.max_y - bounds.min_y;
+        let scale = match (span_x > 0.0, span_y > 0.0) {
+            (true, true) => f64::min(available_width / span_x, available_height / span_y),
+            (true, false) => available_width / span_x,
+            (false, true) => available_height / span_y,
+            // a single point, whatever the scale it lands in the middle
+            (false, false) => 1.0,
+        };
+        Self {
+            bounds: bounds.clone(),
+            scale,
+            width,
+            height,
+        }
+    }
+
+    pub fn to_pixel(&self, point: &Point2d) -> Vec2d {
+        let center_x = (self.bounds.min_x + self.bounds.max_x) / 2.0;
+        let center_y = (self.bounds.min_y + self.bounds.max_y) / 2.0;
+        vec2![
+            self.width as f64 / 2.0 + (point.x - center_x) * self.scale,
+            self.height as f64 / 2.0 + (point.y - center_y) * self.scale
+        ]
+    }
+
+    /// One cell per pixel
+    pub fn grid(&self) -> Grid {
+        let center_x = (self.bounds.min_x + self.bounds.max_x) / 2.0;
+        let center_y = (self.bounds.min_y + self.bounds.max_y) / 2.0;
+        let origin = Point2d::new(
+            center_x - self.width as f64 / 2.0 / self.scale,
+            center_y - self.height as f64 / 2.0 / self.scale,
+        );
+        Grid {
+            origin,
+            cell_size: 1.0 / self.scale,
+            columns: self.width,
+            rows: self.height,
+        }
+    }
+
+    fn fill(&self, img: &mut Image, polygons: &[Polygon], color: &Color) {
+        let mask = self.grid().mask(polygons, FillRule::NonZero);
+        for row in 0..mask.rows {
+            for column in 0..mask.columns {
+                if mask.get(row, column) {
+                    img.set(column, row, color);
+                }
+            }
+        }
+    }
+
+    fn stroke(&self, img: &mut Image, polygon: &Polygon, color: &Color) {
         let points = polygon
             .iter_points()
-            .map(|point| vec2![point.x, point.y + y_offset] * scale)
+            .map(|point| self.to_pixel(point))
             .collect();
         let mut polygon = RustVisionPolygon::from_points(points);
-        polygon.set_color(rgb!(255, 255, 255));
+        polygon.set_color(*color);
         polygon.set_filled(false);
-        polygon
+        img.draw(&polygon);
+    }
+
+    fn arrows(&self, img: &mut Image, polygon: &Polygon, color: &Color) {
+        for segment in polygon.segments.iter() {
+            let start = self.to_pixel(&segment.start);
+            let end = self.to_pixel(&segment.end);
+            let (dx, dy) = (end.x - start.x, end.y - start.y);
+            let length = dx.hypot(dy);
+            if length < ARROW_SIZE {
+                continue;
+            }
+            let (dx, dy) = (dx / length, dy / length);
+            let tip = vec2![
+                (start.x + end.x + dx * ARROW_SIZE) / 2.0,
+                (start.y + end.y + dy * ARROW_SIZE) / 2.0
+            ];
+            for side in [-1.0, 1.0] {
+                let barb = vec2![
+                    tip.x - (dx + side * dy / 2.0) * ARROW_SIZE,
+                    tip.y - (dy - side * dx / 2.0) * ARROW_SIZE
+                ];
+                img.draw(&Line::new(tip, barb).with_color(*color));
+            }
+        }
+    }
+
+    fn vertices(&self, img: &mut Image, polygon: &Polygon, color: &Color) {
+        for point in polygon.iter_points() {
+            let pixel = self.to_pixel(point);
+            for dy in -DOT_RADIUS..=DOT_RADIUS {
+                for dx in -DOT_RADIUS..=DOT_RADIUS {
+                    set(img, pixel.x as i64 + dx, pixel.y as i64 + dy, color);
+                }
+            }
+        }
+    }
+
+    fn labels(&self, img: &mut Image, polygon: &Polygon, color: &Color) {
+        let offset = DOT_RADIUS + 2;
+        for (index, point) in polygon.iter_points().enumerate() {
+            let pixel = self.to_pixel(point);
+            write(
+                img,
+                &index.to_string(),
+                pixel.x as i64 + offset,
+                pixel.y as i64 + offset,
+                color,
+            );
+        }
     }
 }
 
+/// [`draw_sized`] to an 800 by 800 image
 pub fn draw(polygons: &[Polygon]) {
-    let mut img = Image::new(800, 800);
+    draw_sized(polygons, 800, 800);
+}
+
+/// Outlines of `polygons`, all of them in view, saved to `clip.ppm`
+pub fn draw_sized(polygons: &[Polygon], width: usize, height: usize) {
+    let layer = Layer {
+        polygons: polygons.to_vec(),
+        style: Style::default(),
+    };
+    draw_layers(&[layer], width, height);
+}
+
+/// [`render`] saved to `clip.ppm`
+pub fn draw_layers(layers: &[Layer], width: usize, height: usize) {
+    let img = render(layers, width, height);
+    save_pnm_p6!("clip.ppm", img);
+}
+
+/// Every layer on a black background, all of them in view. Fills go first, for the outlines and
+/// markers of the layers below to show through the fills of the ones above.
+pub fn render(layers: &[Layer], width: usize, height: usize) -> Image {
+    let mut img = Image::new(width, height);
     img.fill_with(&rgb!(0, 0, 0));
-    for polygon in polygons {
-        let polygon: RustVisionPolygon = polygon.into();
-        img.draw(&polygon);
+    let polygons = layers.iter().flat_map(|layer| layer.polygons.iter());
+    let Some(viewport) = viewport(polygons, width, height) else {
+        return img;
+    };
+
+    for layer in layers {
+        if let Some(fill) = &layer.style.fill {
+            viewport.fill(&mut img, &layer.polygons, fill);
+        }
+    }
+    for layer in layers {
+        let style = &layer.style;
+        if let Some(stroke) = &style.stroke {
+            for polygon in layer.polygons.iter() {
+                viewport.stroke(&mut img, polygon, stroke);
+            }
+        }
+        let Some(marker) = style.stroke.or(style.fill) else {
+            continue;
+        };
+        for polygon in layer.polygons.iter() {
+            if style.arrows {
+                viewport.arrows(&mut img, polygon, &marker);
+            }
+            if style.vertices {
+                viewport.vertices(&mut img, polygon, &marker);
+            }
+            if style.labels {
+                viewport.labels(&mut img, polygon, &marker);
+            }
+        }
+    }
+    img
+}
+
+/// [`Viewport::fit`] to the union of the bounds of `polygons`, `None` if there are none
+pub fn viewport<'a>(
+    polygons: impl IntoIterator<Item = &'a Polygon>,
+    width: usize,
+    height: usize,
+) -> Option<Viewport> {
+    let bounds = polygons
+        .into_iter()
+        .map(|polygon| polygon.bounds.clone())
+        .reduce(|union, bounds| union.union(&bounds))?;
+    Some(Viewport::fit(&bounds, width, height))
+}
+
+// like Image::set, ignoring pixels off the left or the top of the image as well
+fn set(img: &mut Image, x: i64, y: i64, color: &Color) {
+    if x >= 0 && y >= 0 {
+        img.set(x as usize, y as usize, color);
+    }
+}
+
+// `text` made of digits, with its top left corner at (x, y)
+fn write(img: &mut Image, text: &str, x: i64, y: i64, color: &Color) {
+    let digits = text.chars().filter_map(|c| c.to_digit(10));
+    for (position, digit) in digits.enumerate() {
+        let left = x + position as i64 * 4 * GLYPH_SCALE;
+        for (row, bits) in DIGITS[digit as usize].iter().enumerate() {
+            for column in 0..3 {
+                if bits & (0b100 >> column) == 0 {
+                    continue;
+                }
+                for dy in 0..GLYPH_SCALE {
+                    for dx in 0..GLYPH_SCALE {
+                        let pixel_x = left + column * GLYPH_SCALE + dx;
+                        let pixel_y = y + row as i64 * GLYPH_SCALE + dy;
+                        set(img, pixel_x, pixel_y, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        draw::{self, Layer, Style},
+        point::Point2d,
+        polygon::Polygon,
+    };
+    use rustvision::{color::Color, rgb};
+
+    #[test]
+    fn test_viewport_fits_the_polygons() {
+        let p0 = Point2d::new(-1000.0, 5.0);
+        let p1 = Point2d::new(-1000.0, 6.0);
+        let p2 = Point2d::new(-998.0, 6.0);
+        let first = Polygon::from_points(vec![p0, p1, p2]);
+        let p3 = Point2d::new(-997.0, 4.0);
+        let p4 = Point2d::new(-996.0, 5.0);
+        let p5 = Point2d::new(-996.0, 4.0);
+        let second = Polygon::from_points(vec![p3, p4, p5]);
+
+        // 4 by 2, limited by the width: 360 pixels for 4 units
+        let viewport = draw::viewport(&[first, second], 400, 300).unwrap();
+        assert_eq!(viewport.scale, 90.0);
+        let top_left = viewport.to_pixel(&Point2d::new(-1000.0, 4.0));
+        assert_eq!((top_left.x, top_left.y), (20.0, 60.0));
+        let bottom_right = viewport.to_pixel(&Point2d::new(-996.0, 6.0));
+        assert_eq!((bottom_right.x, bottom_right.y), (380.0, 240.0));
+
+        assert!(draw::viewport(&Vec::new(), 400, 300).is_none());
+    }
+
+    #[test]
+    fn test_styles() {
+        let square = |min: f64, max: f64| {
+            let p0 = Point2d::new(min, min);
+            let p1 = Point2d::new(min, max);
+            let p2 = Point2d::new(max, max);
+            let p3 = Point2d::new(max, min);
+            vec![p0, p1, p2, p3]
+        };
+        let mut hole = square(1.0, 3.0);
+        hole.reverse();
+        let ring = vec![
+            Polygon::from_points(square(0.0, 4.0)),
+            Polygon::from_points(hole),
+        ];
+        let (red, blue, black) = (rgb!(255, 0, 0), rgb!(0, 0, 255), rgb!(0, 0, 0));
+        let style = Style {
+            stroke: Some(red),
+            fill: Some(blue),
+            vertices: false,
+            arrows: false,
+            labels: false,
+        };
+        let layer = |style: &Style| Layer {
+            polygons: ring.clone(),
+            style: style.clone(),
+        };
+        let count = |img: &rustvision::image::Image, color: Color| {
+            (0..100)
+                .flat_map(|y| (0..100).map(move |x| (x, y)))
+                .filter(|(x, y)| img.get(*x, *y) == Some(color))
+                .count()
+        };
+
+        // 15 pixels per unit, (0, 0) at (20, 20)
+        let img = draw::render(&[layer(&style)], 100, 100);
+        assert_eq!(img.get(27, 27), Some(blue));
+        assert_eq!(img.get(50, 50), Some(black));
+        assert_eq!(img.get(20, 20), Some(red));
+        assert_eq!(img.get(10, 10), Some(black));
+        let outlines = count(&img, red);
+
+        let mut with_markers = style.clone();
+        with_markers.vertices = true;
+        let img = draw::render(&[layer(&with_markers)], 100, 100);
+        assert_eq!(img.get(18, 18), Some(red));
+        let dots = count(&img, red);
+        assert!(dots > outlines);
+
+        with_markers.arrows = true;
+        with_markers.labels = true;
+        let img = draw::render(&[layer(&with_markers)], 100, 100);
+        assert!(count(&img, red) > dots);
     }
-    save_pnm_p6!("clip.ppm", img);
 }
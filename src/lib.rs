@@ -1,10 +1,27 @@
+pub mod affine;
 pub mod bounds;
 pub mod clip;
+pub mod convex;
 pub mod draw;
 pub mod iter_from;
+pub mod minkowski;
+pub mod obstacle;
+pub mod offset;
+pub mod overlay;
 pub mod point;
 pub mod polygon;
+pub mod projection;
+pub mod raster;
+pub mod scene;
 pub mod segment;
+pub mod shading;
+pub mod similarity;
+pub mod simplify;
+pub mod simulation;
+pub mod sky;
+pub mod solar;
+pub mod tracker;
+pub mod triangulate;
 pub mod vector;
 
 #[cfg(test)]
@@ -1,4 +1,10 @@
-use polygon_clipping::{clip, draw, point::Point2d, polygon::Polygon};
+use polygon_clipping::{
+    clip,
+    draw::{self, Layer, Style},
+    point::Point2d,
+    polygon::Polygon,
+};
+use rustvision::rgb;
 
 fn main() {
     _case1();
@@ -34,6 +40,24 @@ fn _case1() {
 
     // b is contained in d
     let polygons = vec![polygon_a, polygon_b, polygon_c, polygon_d];
-    let polygons = clip::sum(polygons);
-    draw::draw(&polygons);
+    let sum = clip::sum(polygons.clone());
+
+    let input = Layer {
+        polygons,
+        style: Style {
+            stroke: Some(rgb!(128, 128, 128)),
+            ..Style::default()
+        },
+    };
+    let output = Layer {
+        polygons: sum,
+        style: Style {
+            stroke: Some(rgb!(255, 64, 64)),
+            fill: Some(rgb!(32, 48, 96)),
+            vertices: true,
+            arrows: true,
+            labels: true,
+        },
+    };
+    draw::draw_layers(&[input, output], 800, 800);
 }
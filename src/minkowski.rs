@@ -0,0 +1,171 @@
+use crate::point::Point2d;
+use crate::polygon::Polygon;
+use crate::vector::Vector2d;
+use crate::{clip, convex};
+
+/// Minkowski sum of two polygons: every point of `a` translated by every point of `b`.
+///
+/// Convex polygons are summed directly by merging their edges in order of direction, anything
+/// else is decomposed into convex pieces whose pairwise sums are merged with [`clip::sum`].
+pub fn sum(a: &Polygon, b: &Polygon) -> Vec<Polygon> {
+    if a.is_convex() && b.is_convex() {
+        return vec![convex_sum(a, b)];
+    }
+
+    let pieces_a = convex::decompose(a, &[]);
+    let pieces_b = convex::decompose(b, &[]);
+    let mut sums = Vec::with_capacity(pieces_a.len() * pieces_b.len());
+    for piece_a in &pieces_a {
+        for piece_b in &pieces_b {
+            sums.push(convex_sum(piece_a, piece_b));
+        }
+    }
+
+    clip::sum(sums)
+}
+
+/// Minkowski difference `a ⊖ b`, i.e. the sum of `a` and `b` reflected through the origin. Two
+/// polygons overlap iff their difference contains the origin.
+pub fn difference(a: &Polygon, b: &Polygon) -> Vec<Polygon> {
+    let reflected = b
+        .iter_points()
+        .map(|point| Point2d::new(-point.x, -point.y))
+        .collect();
+    sum(a, &Polygon::from_points(reflected))
+}
+
+fn convex_sum(a: &Polygon, b: &Polygon) -> Polygon {
+    let a = starting_at_lowest_point(a);
+    let b = starting_at_lowest_point(b);
+    let n = a.len();
+    let m = b.len();
+
+    // both polygons now go around clockwise (in y-down) starting from their lowest point, so
+    // their edges are already sorted by direction and only need to be merged
+    let mut points = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        points.push(&a[i % n] + &b[j % m]);
+        let edge_a = Vector2d::from_points(&a[i % n], &a[(i + 1) % n]);
+        let edge_b = Vector2d::from_points(&b[j % m], &b[(j + 1) % m]);
+        let cross = edge_a.cross(&edge_b);
+        if j == m || (i < n && cross > 0.0) {
+            i += 1;
+        } else if i == n || cross < 0.0 {
+            j += 1;
+        } else {
+            i += 1;
+            j += 1;
+        }
+    }
+
+    points.reverse();
+    Polygon::from_points(points)
+}
+
+// points of a convex polygon wound opposite to what Polygon expects, starting at the point with
+// the smallest y (and smallest x among those)
+fn starting_at_lowest_point(polygon: &Polygon) -> Vec<Point2d> {
+    let mut points = polygon.points.clone();
+    if polygon.signed_area() > 0.0 {
+        points.reverse();
+    }
+    let lowest = (0..points.len())
+        .min_by(|&i, &j| {
+            let (p, q) = (&points[i], &points[j]);
+            p.y.partial_cmp(&q.y)
+                .unwrap()
+                .then(p.x.partial_cmp(&q.x).unwrap())
+        })
+        .unwrap();
+    points.rotate_left(lowest);
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{minkowski, point::Point2d, polygon::Polygon};
+    use float_cmp::ApproxEq;
+
+    #[test]
+    fn test_sum_of_a_square_and_a_triangle() {
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 1.0);
+        let p2 = Point2d::new(1.0, 1.0);
+        let p3 = Point2d::new(1.0, 0.0);
+        let points = vec![p0, p1, p2, p3];
+        let square = Polygon::from_points(points);
+
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(1.0, 2.0);
+        let p2 = Point2d::new(2.0, 0.0);
+        let points = vec![p0, p1, p2];
+        let triangle = Polygon::from_points(points);
+
+        let actual_polygons = minkowski::sum(&square, &triangle);
+
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 1.0);
+        let p2 = Point2d::new(1.0, 3.0);
+        let p3 = Point2d::new(2.0, 3.0);
+        let p4 = Point2d::new(3.0, 1.0);
+        let p5 = Point2d::new(3.0, 0.0);
+        let points = vec![p0, p1, p2, p3, p4, p5];
+        let expected_polygon = Polygon::from_points(points);
+        let expected_polygons = vec![expected_polygon];
+
+        assert_eq!(actual_polygons, expected_polygons);
+    }
+
+    #[test]
+    fn test_difference_of_overlapping_polygons_surrounds_the_origin() {
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 2.0);
+        let p2 = Point2d::new(2.0, 2.0);
+        let p3 = Point2d::new(2.0, 0.0);
+        let points = vec![p0, p1, p2, p3];
+        let square = Polygon::from_points(points);
+
+        let p0 = Point2d::new(1.0, 1.0);
+        let p1 = Point2d::new(2.0, 3.0);
+        let p2 = Point2d::new(3.0, 1.0);
+        let points = vec![p0, p1, p2];
+        let triangle = Polygon::from_points(points);
+
+        let difference = minkowski::difference(&square, &triangle);
+
+        assert_eq!(difference.len(), 1);
+        let bounds = &difference[0].bounds;
+        assert_eq!((bounds.min_x, bounds.max_x), (-3.0, 1.0));
+        assert_eq!((bounds.min_y, bounds.max_y), (-3.0, 1.0));
+        assert!(difference[0].signed_area().approx_eq(14.0, (1e-12, 4)));
+    }
+
+    #[test]
+    fn test_sum_of_a_concave_polygon_and_a_square() {
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 2.0);
+        let p2 = Point2d::new(1.0, 2.0);
+        let p3 = Point2d::new(1.0, 1.0);
+        let p4 = Point2d::new(2.0, 1.0);
+        let p5 = Point2d::new(2.0, 0.0);
+        let points = vec![p0, p1, p2, p3, p4, p5];
+        let l_shape = Polygon::from_points(points);
+
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 0.5);
+        let p2 = Point2d::new(0.5, 0.5);
+        let p3 = Point2d::new(0.5, 0.0);
+        let points = vec![p0, p1, p2, p3];
+        let square = Polygon::from_points(points);
+
+        let actual_polygons = minkowski::sum(&l_shape, &square);
+
+        let area = actual_polygons
+            .iter()
+            .map(|polygon| polygon.signed_area())
+            .sum::<f64>();
+        assert_eq!(area, 3.0 + 8.0 * 0.5 / 2.0 + 0.25);
+        assert_eq!(actual_polygons[0].bounds.max_x, 2.5);
+    }
+}
@@ -0,0 +1,234 @@
+use std::f64::consts::TAU;
+
+use crate::convex;
+use crate::point::{Point2d, Point3d};
+use crate::polygon::Polygon;
+use crate::projection::Plane;
+use crate::vector::Vector3d;
+
+// round things are prisms and pyramids over a regular polygon inscribed in their circle
+const CIRCLE_SEGMENTS: usize = 16;
+
+/// A convex solid given by its vertices, which is all it takes to find its shadow
+#[derive(Clone, Debug)]
+pub struct ConvexSolid {
+    pub points: Vec<Point3d>,
+}
+
+impl ConvexSolid {
+    pub fn new(points: Vec<Point3d>) -> Self {
+        Self { points }
+    }
+
+    /// Shadow cast onto `target` when lit from `sun_direction`, in the plane's own coordinates.
+    /// Like [`crate::projection::project_shadow`], only the part of the solid between the sun and
+    /// the plane casts a shadow.
+    pub fn shadow(&self, sun_direction: &Vector3d, target: &Plane) -> Option<Polygon> {
+        let sun_height = sun_direction.dot(&target.normal());
+        if sun_height.abs() < f64::EPSILON * sun_direction.norm() {
+            return None;
+        }
+
+        // cutting the solid by the plane only adds points on edges crossing it, and points on
+        // the segment between any two vertices are inside of the solid anyway
+        let sun_side = sun_height.signum();
+        let heights = self
+            .points
+            .iter()
+            .map(|point| sun_side * target.height_of(point))
+            .collect::<Vec<_>>();
+        let mut lit_part = Vec::with_capacity(self.points.len());
+        for (i, point) in self.points.iter().enumerate() {
+            if heights[i] < 0.0 {
+                continue;
+            }
+            lit_part.push(point.clone());
+            for (j, other) in self.points.iter().enumerate() {
+                if heights[j] < 0.0 {
+                    let t = heights[i] / (heights[i] - heights[j]);
+                    let along = Vector3d::from_points(point, other);
+                    lit_part.push(point + &(&along * t));
+                }
+            }
+        }
+
+        let shadow_points = lit_part
+            .iter()
+            .map(|point| {
+                let t = target.height_of(point) / sun_height;
+                let on_plane = point + &(sun_direction * -t);
+                target.to_local(&on_plane)
+            })
+            .collect::<Vec<_>>();
+        convex::hull(&shadow_points)
+    }
+}
+
+/// Anything other than a panel that casts shadows, made of convex parts, in site coordinates (x
+/// east, y north and z up)
+#[derive(Clone, Debug)]
+pub struct Obstacle {
+    pub parts: Vec<ConvexSolid>,
+}
+
+impl Obstacle {
+    /// `footprint`, in ground coordinates, extruded from `base` up to `base + height`
+    pub fn building(footprint: &Polygon, base: f64, height: f64) -> Self {
+        let parts = convex::decompose(footprint, &[])
+            .iter()
+            .map(|piece| {
+                let floor = piece.iter_points().map(|point| (point, base));
+                let roof = piece.iter_points().map(|point| (point, base + height));
+                let points = floor
+                    .chain(roof)
+                    .map(|(point, z)| Point3d::new(point.x, point.y, z))
+                    .collect();
+                ConvexSolid::new(points)
+            })
+            .collect();
+        Self { parts }
+    }
+
+    /// A cylinder standing on `base`
+    pub fn pole(base: &Point3d, radius: f64, height: f64) -> Self {
+        let top = Point3d::new(base.x, base.y, base.z + height);
+        let mut points = circle(base, radius);
+        points.extend(circle(&top, radius));
+        Self {
+            parts: vec![ConvexSolid::new(points)],
+        }
+    }
+
+    /// A trunk topped by a cone shaped crown
+    pub fn tree(
+        base: &Point3d,
+        trunk_radius: f64,
+        trunk_height: f64,
+        crown_radius: f64,
+        crown_height: f64,
+    ) -> Self {
+        let mut tree = Self::pole(base, trunk_radius, trunk_height);
+        let crown_base = Point3d::new(base.x, base.y, base.z + trunk_height);
+        let mut crown = circle(&crown_base, crown_radius);
+        crown.push(Point3d::new(base.x, base.y, crown_base.z + crown_height));
+        tree.parts.push(ConvexSolid::new(crown));
+        tree
+    }
+
+    /// Ground whose height above `origin` is `heights[row][column]` at `column * spacing` east
+    /// and `row * spacing` north of it, as two triangles per grid cell
+    pub fn terrain(origin: &Point3d, spacing: f64, heights: &[Vec<f64>]) -> Self {
+        let point = |row: usize, column: usize| {
+            Point3d::new(
+                origin.x + column as f64 * spacing,
+                origin.y + row as f64 * spacing,
+                origin.z + heights[row][column],
+            )
+        };
+
+        let mut parts = Vec::new();
+        for row in 1..heights.len() {
+            let columns = heights[row].len().min(heights[row - 1].len());
+            for column in 1..columns {
+                let p0 = point(row - 1, column - 1);
+                let p1 = point(row - 1, column);
+                let p2 = point(row, column);
+                let p3 = point(row, column - 1);
+                parts.push(ConvexSolid::new(vec![p0.clone(), p1, p2.clone()]));
+                parts.push(ConvexSolid::new(vec![p0, p2, p3]));
+            }
+        }
+        Self { parts }
+    }
+
+    /// The shadows of every part, see [`ConvexSolid::shadow`]
+    pub fn shadows(&self, sun_direction: &Vector3d, target: &Plane) -> Vec<Polygon> {
+        self.parts
+            .iter()
+            .filter_map(|part| part.shadow(sun_direction, target))
+            .collect()
+    }
+}
+
+fn circle(center: &Point3d, radius: f64) -> Vec<Point3d> {
+    (0..CIRCLE_SEGMENTS)
+        .map(|i| {
+            let angle = TAU * i as f64 / CIRCLE_SEGMENTS as f64;
+            let offset = Point2d::new(radius * angle.cos(), radius * angle.sin());
+            Point3d::new(center.x + offset.x, center.y + offset.y, center.z)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        obstacle::Obstacle,
+        point::{Point2d, Point3d},
+        polygon::Polygon,
+        projection::Plane,
+        vector::Vector3d,
+    };
+
+    fn ground() -> Plane {
+        let origin = Point3d::new(0.0, 0.0, 0.0);
+        let x = Vector3d::from_coordinates(1.0, 0.0, 0.0);
+        let y = Vector3d::from_coordinates(0.0, 1.0, 0.0);
+        Plane::new(origin, &x, &y)
+    }
+
+    #[test]
+    fn test_building_shadow() {
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 2.0);
+        let p2 = Point2d::new(1.0, 2.0);
+        let p3 = Point2d::new(1.0, 1.0);
+        let p4 = Point2d::new(2.0, 1.0);
+        let p5 = Point2d::new(2.0, 0.0);
+        let l_shape = Polygon::from_points(vec![p0, p1, p2, p3, p4, p5]);
+        let building = Obstacle::building(&l_shape, 0.0, 3.0);
+        assert_eq!(building.parts.len(), 2);
+
+        let overhead = Vector3d::from_coordinates(0.0, 0.0, 1.0);
+        let shadows = building.shadows(&overhead, &ground());
+        let area = shadows.iter().map(Polygon::signed_area).sum::<f64>();
+        assert!((area - 3.0).abs() < 1e-12);
+
+        // sun 45 degrees above the horizon, towards the west: shadows stretch 3 to the east
+        let evening = Vector3d::from_coordinates(-1.0, 0.0, 1.0);
+        let shadows = building.shadows(&evening, &ground());
+        let max_x = shadows
+            .iter()
+            .map(|shadow| shadow.bounds.max_x)
+            .fold(f64::NEG_INFINITY, f64::max);
+        assert!((max_x - 5.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_only_what_is_above_the_plane_casts_a_shadow() {
+        // a pole sunk 1 into the ground
+        let base = Point3d::new(0.0, 0.0, -1.0);
+        let pole = Obstacle::pole(&base, 0.1, 3.0);
+        let evening = Vector3d::from_coordinates(-1.0, 0.0, 1.0);
+        let shadows = pole.shadows(&evening, &ground());
+        assert_eq!(shadows.len(), 1);
+        assert!((shadows[0].bounds.max_x - 2.1).abs() < 1e-12);
+        assert!((shadows[0].bounds.min_x + 0.1).abs() < 1e-12);
+
+        let tree = Obstacle::tree(&Point3d::new(0.0, 0.0, 0.0), 0.2, 2.0, 1.5, 4.0);
+        let shadows = tree.shadows(&evening, &ground());
+        assert_eq!(shadows.len(), 2);
+        assert!((shadows[1].bounds.max_x - 6.0).abs() < 1e-12);
+
+        // a slope rising to 1 along y: nothing of it is above a plane at a height of 2
+        let heights = vec![vec![0.0, 0.0], vec![0.5, 0.5], vec![1.0, 1.0]];
+        let terrain = Obstacle::terrain(&Point3d::new(0.0, 0.0, 0.0), 1.0, &heights);
+        assert_eq!(terrain.parts.len(), 4);
+        let origin = Point3d::new(0.0, 0.0, 2.0);
+        let x = Vector3d::from_coordinates(1.0, 0.0, 0.0);
+        let y = Vector3d::from_coordinates(0.0, 1.0, 0.0);
+        let raised = Plane::new(origin, &x, &y);
+        assert!(terrain.shadows(&evening, &raised).is_empty());
+        assert_eq!(terrain.shadows(&evening, &ground()).len(), 4);
+    }
+}
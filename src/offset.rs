@@ -0,0 +1,348 @@
+use crate::overlay;
+use crate::point::Point2d;
+use crate::polygon::{turn, Polygon};
+use crate::vector::Vector2d;
+use std::f64::consts::PI;
+
+/// How the gap left at a corner is filled when its two edges are moved apart
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JoinStyle {
+    /// extend both edges until they meet, falling back to `Square` for very sharp corners
+    Miter,
+    /// an arc around the original corner
+    Round,
+    /// cut the corner off at `distance` from the original corner
+    Square,
+}
+
+// a miter is never allowed to stick out further than this many times the offset distance
+const MITER_LIMIT: f64 = 2.0;
+// largest angle spanned by a single segment of a round join
+const ROUND_STEP: f64 = PI / 16.0;
+
+impl Polygon {
+    /// Moves every edge `distance` away from the inside of the polygon, growing it for positive
+    /// distances and shrinking it for negative ones. Holes (wound the opposite way) shrink when
+    /// distance is positive, so offsetting every ring of a multipolygon grows the whole of it.
+    ///
+    /// Distances larger than the polygon's smallest feature make the moved edges cross each
+    /// other, so only what they wind around the right way is kept, see [`overlay::untangle`]:
+    /// the result may be made of several rings (outlines followed by their holes, wound as
+    /// usual, or the other way around for a hole) and is empty when shrinking makes the polygon
+    /// vanish.
+    pub fn offset(&self, distance: f64, join: JoinStyle) -> Vec<Polygon> {
+        // a repeated point has no edge to move along
+        let mut corners = self.points.clone();
+        corners.dedup();
+        while corners.len() > 1 && corners.first() == corners.last() {
+            corners.pop();
+        }
+        if corners.len() < 3 {
+            return Vec::new();
+        }
+        if distance == 0.0 {
+            return vec![Polygon::from_points(corners)];
+        }
+
+        // holes are offset as outlines the other way around, and turned back into holes
+        let is_hole = Polygon::from_points(corners.clone()).signed_area() < 0.0;
+        let distance = if is_hole {
+            corners.reverse();
+            -distance
+        } else {
+            distance
+        };
+
+        let n = corners.len();
+        let mut points = Vec::with_capacity(2 * n);
+        // first and last point each corner turned into
+        let mut moved = Vec::with_capacity(n);
+        for i in 0..n {
+            let a = &corners[(i + n - 1) % n];
+            let b = &corners[i];
+            let c = &corners[(i + 1) % n];
+            let first = points.len();
+            offset_corner(a, b, c, distance, join, &mut points);
+            moved.push((first, points.len() - 1));
+        }
+
+        // a polygon shrunk past its centre turns inside out: all of its edges point backwards
+        let every_edge_is_reversed = (0..n).all(|i| {
+            let original = Vector2d::from_points(&corners[i], &corners[(i + 1) % n]);
+            let start = &points[moved[i].1];
+            let end = &points[moved[(i + 1) % n].0];
+            original.dot(&Vector2d::from_points(start, end)) <= 0.0
+        });
+        if every_edge_is_reversed {
+            return Vec::new();
+        }
+
+        let mut rings = overlay::untangle(&Polygon::from_points(points));
+        if is_hole {
+            for ring in rings.iter_mut() {
+                let mut points = std::mem::take(&mut ring.points);
+                points.reverse();
+                *ring = Polygon::from_points(points);
+            }
+        }
+        rings
+    }
+}
+
+fn offset_corner(
+    a: &Point2d,
+    b: &Point2d,
+    c: &Point2d,
+    distance: f64,
+    join: JoinStyle,
+    points: &mut Vec<Point2d>,
+) {
+    let incoming = unit(&Vector2d::from_points(a, b));
+    let outgoing = unit(&Vector2d::from_points(b, c));
+    let incoming_normal = outwards(&incoming);
+    let outgoing_normal = outwards(&outgoing);
+
+    let q1 = shifted(b, &incoming_normal, distance);
+    let q2 = shifted(b, &outgoing_normal, distance);
+
+    let cos_theta = incoming_normal.dot(&outgoing_normal);
+    let is_straight = incoming.cross(&outgoing) == 0.0 && cos_theta > 0.0;
+    if is_straight {
+        points.push(q1);
+        return;
+    }
+
+    // the offset edges overlap instead of leaving a gap, their intersection is the new corner
+    let opens_a_gap = turn(a, b, c) * distance > 0.0;
+    if !opens_a_gap {
+        points.push(miter(b, &incoming_normal, &outgoing_normal, distance));
+        return;
+    }
+
+    match join {
+        JoinStyle::Miter => {
+            let miter_length = 1.0 / ((1.0 + cos_theta) / 2.0).sqrt();
+            if miter_length <= MITER_LIMIT {
+                points.push(miter(b, &incoming_normal, &outgoing_normal, distance));
+            } else {
+                square(b, &incoming, &outgoing, &q1, &q2, distance, points);
+            }
+        }
+        JoinStyle::Square => square(b, &incoming, &outgoing, &q1, &q2, distance, points),
+        JoinStyle::Round => {
+            let start = Vector2d::from_points(b, &q1);
+            let end = Vector2d::from_points(b, &q2);
+            let sweep = start.cross(&end).atan2(start.dot(&end));
+            let steps = (sweep.abs() / ROUND_STEP).ceil().max(1.0) as usize;
+            let start_angle = start.y.atan2(start.x);
+            let radius = distance.abs();
+            points.push(q1);
+            for step in 1..steps {
+                let angle = start_angle + sweep * step as f64 / steps as f64;
+                points.push(Point2d::new(
+                    b.x + radius * angle.cos(),
+                    b.y + radius * angle.sin(),
+                ));
+            }
+            points.push(q2);
+        }
+    }
+}
+
+// where the two offset edges meet
+fn miter(
+    b: &Point2d,
+    incoming_normal: &Vector2d,
+    outgoing_normal: &Vector2d,
+    distance: f64,
+) -> Point2d {
+    let cos_theta = incoming_normal.dot(outgoing_normal);
+    let factor = distance / (1.0 + cos_theta);
+    Point2d::new(
+        b.x + factor * (incoming_normal.x + outgoing_normal.x),
+        b.y + factor * (incoming_normal.y + outgoing_normal.y),
+    )
+}
+
+// both offset edges are extended up to the line at |distance| from the corner, perpendicular to
+// the bisector
+fn square(
+    b: &Point2d,
+    incoming: &Vector2d,
+    outgoing: &Vector2d,
+    q1: &Point2d,
+    q2: &Point2d,
+    distance: f64,
+    points: &mut Vec<Point2d>,
+) {
+    let bisector = unit(&(incoming - outgoing));
+    let extend = |q: &Point2d, along: &Vector2d| {
+        let from_corner = Vector2d::from_points(b, q);
+        let t = (distance.abs() - from_corner.dot(&bisector)) / along.dot(&bisector);
+        Point2d::new(q.x + t * along.x, q.y + t * along.y)
+    };
+    points.push(extend(q1, incoming));
+    points.push(extend(q2, outgoing));
+}
+
+fn unit(vector: &Vector2d) -> Vector2d {
+    let norm = vector.norm();
+    Vector2d::from_coordinates(vector.x / norm, vector.y / norm)
+}
+
+// points away from the inside of a polygon wound the way Polygon expects
+fn outwards(direction: &Vector2d) -> Vector2d {
+    Vector2d::from_coordinates(-direction.y, direction.x)
+}
+
+fn shifted(point: &Point2d, normal: &Vector2d, distance: f64) -> Point2d {
+    Point2d::new(point.x + distance * normal.x, point.y + distance * normal.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JoinStyle;
+    use crate::{point::Point2d, polygon::Polygon};
+    use float_cmp::ApproxEq;
+    use std::f64::consts::PI;
+
+    fn square() -> Polygon {
+        let p0 = Point2d::new(1.0, 1.0);
+        let p1 = Point2d::new(1.0, 3.0);
+        let p2 = Point2d::new(3.0, 3.0);
+        let p3 = Point2d::new(3.0, 1.0);
+        let points = vec![p0, p1, p2, p3];
+        Polygon::from_points(points)
+    }
+
+    fn only(mut rings: Vec<Polygon>) -> Polygon {
+        assert_eq!(rings.len(), 1);
+        rings.pop().unwrap()
+    }
+
+    #[test]
+    fn test_growing_a_square_with_every_join_style() {
+        let square = square();
+
+        let miter = only(square.offset(1.0, JoinStyle::Miter));
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 4.0);
+        let p2 = Point2d::new(4.0, 4.0);
+        let p3 = Point2d::new(4.0, 0.0);
+        let points = vec![p0, p1, p2, p3];
+        assert_eq!(miter, Polygon::from_points(points));
+
+        // each corner loses a triangle of area (sqrt(2) - 1)^2 compared to the miter
+        let square_join = only(square.offset(1.0, JoinStyle::Square));
+        let expected_area = 16.0 - 4.0 * (2f64.sqrt() - 1.0).powi(2);
+        assert!(square_join
+            .signed_area()
+            .approx_eq(expected_area, (1e-12, 4)));
+
+        // the corners become quarter circles, approximated from the inside
+        let round = only(square.offset(1.0, JoinStyle::Round));
+        let area = round.signed_area();
+        assert!(area < 12.0 + PI && area > 12.0 + PI - 0.05);
+    }
+
+    #[test]
+    fn test_growing_a_concave_polygon() {
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 2.0);
+        let p2 = Point2d::new(1.0, 2.0);
+        let p3 = Point2d::new(1.0, 1.0);
+        let p4 = Point2d::new(2.0, 1.0);
+        let p5 = Point2d::new(2.0, 0.0);
+        let points = vec![p0, p1, p2, p3, p4, p5];
+        let l_shape = Polygon::from_points(points);
+
+        let grown = only(l_shape.offset(0.25, JoinStyle::Miter));
+
+        // area + perimeter * d + (convex corners - reflex corners) * d^2
+        assert_eq!(grown.points.len(), 6);
+        assert_eq!(grown.signed_area(), 3.0 + 8.0 * 0.25 + 4.0 * 0.0625);
+        assert_eq!(grown.points[3], Point2d::new(1.25, 1.25));
+    }
+
+    #[test]
+    fn test_shrinking_a_square() {
+        let square = square();
+
+        let shrunk = only(square.offset(-0.5, JoinStyle::Round));
+        let p0 = Point2d::new(1.5, 1.5);
+        let p1 = Point2d::new(1.5, 2.5);
+        let p2 = Point2d::new(2.5, 2.5);
+        let p3 = Point2d::new(2.5, 1.5);
+        let points = vec![p0, p1, p2, p3];
+        assert_eq!(shrunk, Polygon::from_points(points));
+
+        assert!(square.offset(-1.5, JoinStyle::Miter).is_empty());
+    }
+
+    #[test]
+    fn test_offsetting_past_the_smallest_feature() {
+        // a U whose notch is 1 wide: growing it by more than 0.5 fills the notch in, which
+        // leaves the moved edges of the notch crossing each other
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 3.0);
+        let p2 = Point2d::new(1.0, 3.0);
+        let p3 = Point2d::new(1.0, 1.0);
+        let p4 = Point2d::new(2.0, 1.0);
+        let p5 = Point2d::new(2.0, 3.0);
+        let p6 = Point2d::new(3.0, 3.0);
+        let p7 = Point2d::new(3.0, 0.0);
+        let u_shape = Polygon::from_points(vec![p0, p1, p2, p3, p4, p5, p6, p7]);
+
+        let grown = only(u_shape.offset(0.75, JoinStyle::Miter));
+        assert!(grown.signed_area().approx_eq(4.5 * 4.5, (1e-9, 4)));
+
+        // two squares joined by a corridor 0.5 wide, which vanishes when shrinking by 0.5
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 2.0);
+        let p2 = Point2d::new(2.0, 2.0);
+        let p3 = Point2d::new(2.0, 1.25);
+        let p4 = Point2d::new(3.0, 1.25);
+        let p5 = Point2d::new(3.0, 2.0);
+        let p6 = Point2d::new(5.0, 2.0);
+        let p7 = Point2d::new(5.0, 0.0);
+        let p8 = Point2d::new(3.0, 0.0);
+        let p9 = Point2d::new(3.0, 0.75);
+        let p10 = Point2d::new(2.0, 0.75);
+        let p11 = Point2d::new(2.0, 0.0);
+        let points = vec![p0, p1, p2, p3, p4, p5, p6, p7, p8, p9, p10, p11];
+        let dumbbell = Polygon::from_points(points);
+
+        let shrunk = dumbbell.offset(-0.5, JoinStyle::Miter);
+        assert_eq!(shrunk.len(), 2);
+        for piece in shrunk {
+            assert!(piece.signed_area().approx_eq(1.0, (1e-9, 4)));
+        }
+
+        // a hole grown by the same amount shrinks into two holes
+        let mut points = dumbbell.points.clone();
+        points.reverse();
+        let hole = Polygon::from_points(points);
+        let shrunk = hole.offset(0.5, JoinStyle::Miter);
+        assert_eq!(shrunk.len(), 2);
+        for piece in shrunk {
+            assert!(piece.signed_area().approx_eq(-1.0, (1e-9, 4)));
+        }
+    }
+
+    #[test]
+    fn test_offsetting_with_repeated_points() {
+        let p0 = Point2d::new(1.0, 1.0);
+        let p1 = Point2d::new(1.0, 3.0);
+        let p2 = Point2d::new(3.0, 3.0);
+        let p3 = Point2d::new(3.0, 1.0);
+        let points = vec![p0.clone(), p1.clone(), p1, p2, p3, p0];
+        let square_with_repeats = Polygon::from_points(points);
+
+        let grown = only(square_with_repeats.offset(1.0, JoinStyle::Round));
+
+        assert!(grown
+            .iter_points()
+            .all(|point| point.x.is_finite() && point.y.is_finite()));
+        assert_eq!(grown, only(square().offset(1.0, JoinStyle::Round)));
+    }
+}
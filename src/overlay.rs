@@ -0,0 +1,667 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::point::Point2d;
+use crate::polygon::{winding_number, Polygon};
+use crate::triangulate::Triangulation;
+use crate::vector::Vector2d;
+
+/// A face of the planar arrangement of some polygons: a region none of their edges runs through,
+/// the indices of the inputs covering it, in increasing order, and its holes, wound the other way
+/// around as usual
+#[derive(Clone, Debug)]
+pub struct Face {
+    pub outline: Polygon,
+    pub holes: Vec<Polygon>,
+    pub covered_by: Vec<usize>,
+}
+
+impl Face {
+    pub fn area(&self) -> f64 {
+        area(&self.outline, &self.holes)
+    }
+}
+
+/// Cuts the plane along the edges of every polygon and returns the bounded faces, each with the
+/// inputs that cover it, including the faces no input covers (e.g. the hole in a ring of
+/// overlapping polygons). Every input is a single ring, whichever way it is wound.
+///
+/// Unlike [`crate::clip::sum`], which only keeps the outline of the union, this tells where every
+/// part of the union comes from.
+pub fn overlay(polygons: &[Polygon]) -> Vec<Face> {
+    Arrangement::new(polygons)
+        .faces()
+        .bounded
+        .into_iter()
+        .map(|(outline, holes)| {
+            let covered_by = covered_by(polygons, &outline, &holes);
+            Face {
+                outline,
+                holes,
+                covered_by,
+            }
+        })
+        .collect()
+}
+
+/// A region of the plane where the inputs covering it all share the same label
+#[derive(Clone, Debug)]
+pub struct Region<L> {
+    pub outline: Polygon,
+    pub holes: Vec<Polygon>,
+    pub label: L,
+}
+
+impl<L> Region<L> {
+    pub fn area(&self) -> f64 {
+        area(&self.outline, &self.holes)
+    }
+}
+
+/// Like [`overlay`], but neighbouring faces merge whenever `label` gives them the same label
+/// from the indices of the inputs covering them. The regions outside of every input are labelled
+/// from no inputs at all.
+pub fn dissolve<L: PartialEq>(
+    polygons: &[Polygon],
+    label: impl Fn(&[usize]) -> L,
+) -> Vec<Region<L>> {
+    dissolve_faces(polygons, label(&[]), |outline, holes| {
+        label(&covered_by(polygons, outline, holes))
+    })
+}
+
+/// The parts of the plane `polygon` winds around the way [`Polygon`] expects, once or more,
+/// which untangles a ring crossing itself into rings that don't: outlines, each followed by its
+/// holes, wound the other way around as usual. Nothing is left of a ring wound the other way.
+pub fn untangle(polygon: &Polygon) -> Vec<Polygon> {
+    let is_inside = |outline: &Polygon, holes: &[Polygon]| {
+        let inside = sample_point(outline, holes);
+        winding_number(&polygon.points, &inside) > 0
+    };
+
+    let mut rings = Vec::new();
+    for region in dissolve_faces(std::slice::from_ref(polygon), false, is_inside) {
+        if region.label {
+            rings.push(region.outline);
+            rings.extend(region.holes);
+        }
+    }
+    rings
+}
+
+// the faces of the arrangement of `polygons`, merged whenever `label` gives neighbours the same
+// label from their outline and holes, `outside` being the label of the unbounded face
+fn dissolve_faces<L: PartialEq>(
+    polygons: &[Polygon],
+    outside: L,
+    label: impl Fn(&Polygon, &[Polygon]) -> L,
+) -> Vec<Region<L>> {
+    let arrangement = Arrangement::new(polygons);
+    let Faces { bounded, face_of } = arrangement.faces();
+    let labels = bounded
+        .iter()
+        .map(|(outline, holes)| label(outline, holes))
+        .collect::<Vec<_>>();
+    let label_of = |face: Option<usize>| face.map_or(&outside, |face| &labels[face]);
+
+    // an edge stays if it separates different labels
+    let dissolved = arrangement.keeping(|vertex, k| {
+        let next = arrangement.neighbours[vertex][k];
+        let back = arrangement.back(vertex, k);
+        label_of(face_of[vertex][k]) != label_of(face_of[next][back])
+    });
+
+    dissolved
+        .faces()
+        .bounded
+        .into_iter()
+        .map(|(outline, holes)| {
+            let label = label(&outline, &holes);
+            Region {
+                outline,
+                holes,
+                label,
+            }
+        })
+        .collect()
+}
+
+/// How many of `polygons` overlap where: the regions covered by at least one of them, labelled
+/// with the number of polygons covering them
+pub fn coverage_depth(polygons: &[Polygon]) -> Vec<Region<usize>> {
+    dissolve(polygons, |covered_by| covered_by.len())
+        .into_iter()
+        .filter(|region| region.label > 0)
+        .collect()
+}
+
+/// The regions shaded by at least one of `polygons`, each letting `transmittances[i]` of the light
+/// through (1 minus its opacity), labelled with the share of the light that gets through all of
+/// the polygons covering them: the product of their transmittances
+///
+/// Panics unless there is exactly one transmittance per polygon.
+pub fn transmittance(polygons: &[Polygon], transmittances: &[f64]) -> Vec<Region<f64>> {
+    assert_eq!(
+        polygons.len(),
+        transmittances.len(),
+        "there must be one transmittance per polygon"
+    );
+
+    dissolve(polygons, |covered_by| {
+        covered_by
+            .iter()
+            .map(|&index| transmittances[index])
+            .product::<f64>()
+    })
+    .into_iter()
+    .filter(|region| region.label < 1.0)
+    .collect()
+}
+
+// the indices of the inputs covering a face
+fn covered_by(polygons: &[Polygon], outline: &Polygon, holes: &[Polygon]) -> Vec<usize> {
+    let inside = sample_point(outline, holes);
+    polygons
+        .iter()
+        .enumerate()
+        .filter(|(_, polygon)| polygon.contains(&inside))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+// vertices and edges that only meet at their ends
+struct Arrangement {
+    points: Vec<Point2d>,
+    // sorted by angle around every vertex
+    neighbours: Vec<Vec<usize>>,
+}
+
+impl Arrangement {
+    fn new(polygons: &[Polygon]) -> Self {
+        let segments = polygons
+            .iter()
+            .flat_map(|polygon| polygon.segments.iter())
+            .filter(|segment| !segment.is_point())
+            .collect::<Vec<_>>();
+
+        let mut order = (0..segments.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| {
+            let a = segments[a].bounds().min_x;
+            let b = segments[b].bounds().min_x;
+            a.total_cmp(&b)
+        });
+
+        // where every segment is cut, as fractions of its length
+        let mut cuts = vec![vec![0.0, 1.0]; segments.len()];
+        for (k, &i) in order.iter().enumerate() {
+            let max_x = segments[i].bounds().max_x;
+            for &j in order[k + 1..].iter() {
+                if segments[j].bounds().min_x > max_x {
+                    break;
+                }
+                if !segments[i].intersects(segments[j]) {
+                    continue;
+                }
+
+                let along_i: Vector2d = segments[i].into();
+                let along_j: Vector2d = segments[j].into();
+                let start_to_start = Vector2d::from_points(&segments[i].start, &segments[j].start);
+                let denominator = along_i.cross(&along_j);
+                if denominator != 0.0 {
+                    let t = start_to_start.cross(&along_j) / denominator;
+                    let u = start_to_start.cross(&along_i) / denominator;
+                    cuts[i].push(t.clamp(0.0, 1.0));
+                    cuts[j].push(u.clamp(0.0, 1.0));
+                    continue;
+                }
+
+                // overlapping collinear segments cut each other at their ends
+                let project = |point: &Point2d, along: &Vector2d, start: &Point2d| {
+                    Vector2d::from_points(start, point).dot(along) / along.norm_sq()
+                };
+                for point in [&segments[j].start, &segments[j].end] {
+                    cuts[i].push(project(point, &along_i, &segments[i].start).clamp(0.0, 1.0));
+                }
+                for point in [&segments[i].start, &segments[i].end] {
+                    cuts[j].push(project(point, &along_j, &segments[j].start).clamp(0.0, 1.0));
+                }
+            }
+        }
+
+        let scale = polygons
+            .iter()
+            .map(|polygon| {
+                let bounds = &polygon.bounds;
+                bounds
+                    .max_x
+                    .abs()
+                    .max(bounds.min_x.abs())
+                    .max(bounds.max_y.abs())
+                    .max(bounds.min_y.abs())
+            })
+            .fold(1.0, f64::max);
+        let mut vertices = Vertices::new(1e-9 * scale);
+        let mut edges = HashSet::new();
+        for (segment, mut cuts) in segments.iter().zip(cuts) {
+            cuts.sort_by(f64::total_cmp);
+            let along: Vector2d = (*segment).into();
+            let ids = cuts
+                .iter()
+                .map(|t| {
+                    let x = segment.start.x + t * along.x;
+                    let y = segment.start.y + t * along.y;
+                    vertices.id(Point2d::new(x, y))
+                })
+                .collect::<Vec<_>>();
+            for pair in ids.windows(2) {
+                if pair[0] != pair[1] {
+                    edges.insert((pair[0].min(pair[1]), pair[0].max(pair[1])));
+                }
+            }
+        }
+
+        let points = vertices.points;
+        let mut neighbours = vec![Vec::new(); points.len()];
+        for (a, b) in edges {
+            neighbours[a].push(b);
+            neighbours[b].push(a);
+        }
+        for (vertex, around) in neighbours.iter_mut().enumerate() {
+            let angle = |other: &usize| {
+                let to_other = Vector2d::from_points(&points[vertex], &points[*other]);
+                to_other.y.atan2(to_other.x)
+            };
+            around.sort_by(|a, b| angle(a).total_cmp(&angle(b)));
+        }
+
+        Self { points, neighbours }
+    }
+
+    // every cycle of half-edges, given as a vertex and the position of the next one around it,
+    // keeping the face on the same side: bounded faces come out wound the way Polygon expects and
+    // the outer boundary of every connected piece the other way around
+    fn cycles(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut visited = self
+            .neighbours
+            .iter()
+            .map(|around| vec![false; around.len()])
+            .collect::<Vec<_>>();
+
+        let mut cycles = Vec::new();
+        for start in 0..self.points.len() {
+            for first in 0..self.neighbours[start].len() {
+                if visited[start][first] {
+                    continue;
+                }
+
+                let mut cycle = Vec::new();
+                let (mut vertex, mut k) = (start, first);
+                while !visited[vertex][k] {
+                    visited[vertex][k] = true;
+                    cycle.push((vertex, k));
+                    let next = self.neighbours[vertex][k];
+                    (vertex, k) = (
+                        next,
+                        (self.back(vertex, k) + 1) % self.neighbours[next].len(),
+                    );
+                }
+                cycles.push(cycle);
+            }
+        }
+        cycles
+    }
+
+    // the position of the vertex around its k-th neighbour
+    fn back(&self, vertex: usize, k: usize) -> usize {
+        let next = self.neighbours[vertex][k];
+        self.neighbours[next]
+            .iter()
+            .position(|&other| other == vertex)
+            .unwrap()
+    }
+
+    // the bounded faces with their holes, and which of them is on the side of every half-edge
+    fn faces(&self) -> Faces {
+        let component = self.components();
+        let mut outlines = Vec::new();
+        let mut boundaries = Vec::new();
+        let mut face_of = self
+            .neighbours
+            .iter()
+            .map(|around| vec![None; around.len()])
+            .collect::<Vec<_>>();
+        for cycle in self.cycles() {
+            let points = cycle.iter().map(|&(i, _)| self.points[i].clone()).collect();
+            let polygon = Polygon::from_points(points);
+            if polygon.signed_area() > 0.0 {
+                for &(vertex, k) in cycle.iter() {
+                    face_of[vertex][k] = Some(outlines.len());
+                }
+                outlines.push((polygon, Vec::new(), component[cycle[0].0]));
+            } else if polygon.signed_area() < 0.0 {
+                boundaries.push((polygon, cycle));
+            }
+        }
+
+        // the outer boundary of a piece is a hole in the smallest face of another piece around it
+        for (boundary, cycle) in boundaries {
+            let boundary_component = component[cycle[0].0];
+            let around = outlines
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, _, component))| *component != boundary_component)
+                .filter(|(_, (outline, _, _))| outline.contains(&boundary.points[0]))
+                .min_by(|(_, (a, _, _)), (_, (b, _, _))| {
+                    a.signed_area().total_cmp(&b.signed_area())
+                })
+                .map(|(i, _)| i);
+            if let Some(i) = around {
+                for &(vertex, k) in cycle.iter() {
+                    face_of[vertex][k] = Some(i);
+                }
+                outlines[i].1.push(boundary);
+            }
+        }
+        let bounded = outlines
+            .into_iter()
+            .map(|(outline, holes, _)| (outline, holes))
+            .collect();
+        Faces { bounded, face_of }
+    }
+
+    // the same vertices with only the edges for which `keep` holds, given a vertex and the
+    // position of the other end around it
+    fn keeping(&self, keep: impl Fn(usize, usize) -> bool) -> Self {
+        let neighbours = self
+            .neighbours
+            .iter()
+            .enumerate()
+            .map(|(vertex, around)| {
+                (0..around.len())
+                    .filter(|&k| keep(vertex, k))
+                    .map(|k| around[k])
+                    .collect()
+            })
+            .collect();
+        Self {
+            points: self.points.clone(),
+            neighbours,
+        }
+    }
+
+    // which connected piece every vertex belongs to
+    fn components(&self) -> Vec<usize> {
+        let mut component = vec![usize::MAX; self.points.len()];
+        for start in 0..self.points.len() {
+            if component[start] != usize::MAX {
+                continue;
+            }
+            let mut stack = vec![start];
+            component[start] = start;
+            while let Some(vertex) = stack.pop() {
+                for &next in self.neighbours[vertex].iter() {
+                    if component[next] == usize::MAX {
+                        component[next] = start;
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+        component
+    }
+}
+
+// the faces of an arrangement
+struct Faces {
+    // the bounded ones with their holes
+    bounded: Vec<(Polygon, Vec<Polygon>)>,
+    // which of them is on the side of every half-edge, given as a vertex and the position of the
+    // other end around it (None for the unbounded face)
+    face_of: Vec<Vec<Option<usize>>>,
+}
+
+// points closer than a tolerance are the same vertex
+struct Vertices {
+    points: Vec<Point2d>,
+    grid: HashMap<(i64, i64), Vec<usize>>,
+    tolerance: f64,
+}
+
+impl Vertices {
+    fn new(tolerance: f64) -> Self {
+        Self {
+            points: Vec::new(),
+            grid: HashMap::new(),
+            tolerance,
+        }
+    }
+
+    fn id(&mut self, point: Point2d) -> usize {
+        let cell_x = (point.x / self.tolerance).floor() as i64;
+        let cell_y = (point.y / self.tolerance).floor() as i64;
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let Some(ids) = self.grid.get(&(cell_x + dx, cell_y + dy)) else {
+                    continue;
+                };
+                let same = ids
+                    .iter()
+                    .find(|&&id| self.points[id].distance_to(&point) <= self.tolerance);
+                if let Some(&id) = same {
+                    return id;
+                }
+            }
+        }
+
+        let id = self.points.len();
+        self.points.push(point);
+        self.grid.entry((cell_x, cell_y)).or_default().push(id);
+        id
+    }
+}
+
+fn area(outline: &Polygon, holes: &[Polygon]) -> f64 {
+    let holes = holes.iter().map(Polygon::signed_area).sum::<f64>();
+    outline.signed_area() + holes
+}
+
+// a point well inside of the face: the centroid of its largest triangle
+fn sample_point(outline: &Polygon, holes: &[Polygon]) -> Point2d {
+    let triangulation = Triangulation::ear_clipping(outline, holes);
+    let largest = triangulation
+        .to_polygons()
+        .into_iter()
+        .max_by(|a, b| a.signed_area().abs().total_cmp(&b.signed_area().abs()));
+    let corners = match largest {
+        Some(triangle) => triangle.points,
+        None => outline.points.clone(),
+    };
+    let n = corners.len() as f64;
+    let x = corners.iter().map(|point| point.x).sum::<f64>() / n;
+    let y = corners.iter().map(|point| point.y).sum::<f64>() / n;
+    Point2d::new(x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        overlay::{self, overlay},
+        point::Point2d,
+        polygon::Polygon,
+    };
+
+    fn square(x: f64, y: f64, size: f64) -> Polygon {
+        let p0 = Point2d::new(x, y);
+        let p1 = Point2d::new(x, y + size);
+        let p2 = Point2d::new(x + size, y + size);
+        let p3 = Point2d::new(x + size, y);
+        Polygon::from_points(vec![p0, p1, p2, p3])
+    }
+
+    #[test]
+    fn test_two_overlapping_squares() {
+        let faces = overlay(&[square(0.0, 0.0, 2.0), square(1.0, 1.0, 2.0)]);
+
+        assert_eq!(faces.len(), 3);
+        let mut covered = faces
+            .iter()
+            .map(|face| (face.covered_by.clone(), face.area()))
+            .collect::<Vec<_>>();
+        covered.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            covered,
+            vec![(vec![0], 3.0), (vec![0, 1], 1.0), (vec![1], 3.0)]
+        );
+    }
+
+    #[test]
+    fn test_nested_and_touching_squares() {
+        // a small square inside of a big one and another one sharing half of an edge with it
+        let big = square(0.0, 0.0, 4.0);
+        let inner = square(1.0, 1.0, 1.0);
+        let mut reversed = square(4.0, 1.0, 2.0).points;
+        reversed.reverse();
+        let beside = Polygon::from_points(reversed);
+
+        let faces = overlay(&[big, inner, beside]);
+
+        assert_eq!(faces.len(), 3);
+        let ring = faces
+            .iter()
+            .find(|face| face.covered_by == vec![0])
+            .unwrap();
+        assert_eq!(ring.holes.len(), 1);
+        assert_eq!(ring.area(), 15.0);
+        let inner = faces
+            .iter()
+            .find(|face| face.covered_by == vec![0, 1])
+            .unwrap();
+        assert_eq!(inner.area(), 1.0);
+        let beside = faces
+            .iter()
+            .find(|face| face.covered_by == vec![2])
+            .unwrap();
+        assert_eq!(beside.area(), 4.0);
+        // the big square's right edge is cut where the other one touches it
+        assert_eq!(ring.outline.points.len(), 6);
+    }
+
+    #[test]
+    fn test_uncovered_faces_are_kept() {
+        // four bars around a square gap
+        let bar = |min_x: f64, min_y: f64, max_x: f64, max_y: f64| {
+            let p0 = Point2d::new(min_x, min_y);
+            let p1 = Point2d::new(min_x, max_y);
+            let p2 = Point2d::new(max_x, max_y);
+            let p3 = Point2d::new(max_x, min_y);
+            Polygon::from_points(vec![p0, p1, p2, p3])
+        };
+        let bars = [
+            bar(0.0, 0.0, 3.0, 1.0),
+            bar(0.0, 2.0, 3.0, 3.0),
+            bar(0.0, 0.0, 1.0, 3.0),
+            bar(2.0, 0.0, 3.0, 3.0),
+        ];
+
+        let faces = overlay(&bars);
+
+        let gap = faces
+            .iter()
+            .find(|face| face.covered_by.is_empty())
+            .unwrap();
+        assert_eq!(gap.area(), 1.0);
+        let total = faces
+            .iter()
+            .filter(|face| !face.covered_by.is_empty())
+            .map(|face| face.area())
+            .sum::<f64>();
+        assert_eq!(total, 8.0);
+    }
+
+    #[test]
+    fn test_coverage_depth() {
+        // three squares overlapping in a staircase, the first and last ones only at a corner
+        let squares = [
+            square(0.0, 0.0, 2.0),
+            square(1.0, 1.0, 2.0),
+            square(2.0, 2.0, 2.0),
+        ];
+
+        let regions = overlay::coverage_depth(&squares);
+
+        let area_at = |depth: usize| {
+            regions
+                .iter()
+                .filter(|region| region.label == depth)
+                .map(|region| region.area())
+                .sum::<f64>()
+        };
+        assert_eq!(area_at(1), 8.0);
+        assert_eq!(area_at(2), 2.0);
+        assert_eq!(area_at(3), 0.0);
+        // regions only touching at a corner stay apart: each end square minus its overlap, the
+        // two corners of the middle one and the two overlaps
+        assert_eq!(regions.len(), 6);
+    }
+
+    #[test]
+    fn test_dissolve_by_label() {
+        // a square split in two halves, with a third polygon in the middle of both
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 4.0);
+        let p2 = Point2d::new(2.0, 4.0);
+        let p3 = Point2d::new(2.0, 0.0);
+        let left = Polygon::from_points(vec![p0, p1, p2, p3]);
+        let p0 = Point2d::new(2.0, 0.0);
+        let p1 = Point2d::new(2.0, 4.0);
+        let p2 = Point2d::new(4.0, 4.0);
+        let p3 = Point2d::new(4.0, 0.0);
+        let right = Polygon::from_points(vec![p0, p1, p2, p3]);
+        let middle = square(1.0, 1.0, 2.0);
+
+        let covered =
+            overlay::dissolve(&[left, right, middle], |covered_by| !covered_by.is_empty());
+
+        let covered = covered
+            .iter()
+            .filter(|region| region.label)
+            .collect::<Vec<_>>();
+        assert_eq!(covered.len(), 1);
+        assert_eq!(covered[0].area(), 16.0);
+        assert!(covered[0].holes.is_empty());
+    }
+
+    #[test]
+    fn test_transmittance() {
+        // a glass-glass module letting half of the light through over an opaque pole
+        let module = square(0.0, 0.0, 2.0);
+        let pole = square(1.0, 1.0, 2.0);
+        let clear = square(5.0, 5.0, 1.0);
+
+        let regions = overlay::transmittance(&[module, pole, clear], &[0.5, 0.0, 1.0]);
+
+        let area_at = |transmittance: f64| {
+            regions
+                .iter()
+                .filter(|region| region.label == transmittance)
+                .map(|region| region.area())
+                .sum::<f64>()
+        };
+        assert_eq!(area_at(0.5), 3.0);
+        assert_eq!(area_at(0.0), 4.0);
+        assert_eq!(regions.len(), 2);
+        // what a plain union would count as shaded versus the light actually lost
+        let lost = regions
+            .iter()
+            .map(|region| (1.0 - region.label) * region.area())
+            .sum::<f64>();
+        assert_eq!(lost, 5.5);
+    }
+
+    #[test]
+    #[should_panic(expected = "one transmittance per polygon")]
+    fn test_transmittance_needs_one_value_per_polygon() {
+        let module = square(0.0, 0.0, 2.0);
+        let pole = square(1.0, 1.0, 2.0);
+        overlay::transmittance(&[module, pole], &[0.5]);
+    }
+}
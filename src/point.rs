@@ -23,6 +23,10 @@ impl Point2d {
         Self { x, y, key }
     }
 
+    pub fn distance_to(&self, other: &Point2d) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+
     pub fn is_inside_of_old(&self, mould_segments: &[Segment]) -> bool {
         // if all vectors from segment[i].start to self are pointing inwards
         let up = -Vector3d::z();
@@ -160,6 +164,14 @@ impl Point3d {
     }
 }
 
+impl Add<&Vector3d> for &Point3d {
+    type Output = Point3d;
+
+    fn add(self, vector: &Vector3d) -> Self::Output {
+        Point3d::new(self.x + vector.x, self.y + vector.y, self.z + vector.z)
+    }
+}
+
 impl From<Point3d> for Point2d {
     fn from(point: Point3d) -> Self {
         let x = point.x;
@@ -2,7 +2,9 @@ use crate::bounds::Bounds;
 use crate::iter_from::IteratorFrom;
 use crate::point::Point2d;
 use crate::segment::Segment;
-use std::collections::HashMap;
+use crate::vector::Vector2d;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
 use std::fmt::Display;
 use std::slice::Iter;
 
@@ -48,6 +50,314 @@ impl Polygon {
     pub fn iter_points(&self) -> Iter<'_, Point2d> {
         self.points.iter()
     }
+
+    /// Shoelace area, positive when the points follow the winding documented on [`Polygon`]
+    /// and negative otherwise (which is how holes are told apart from outer boundaries)
+    pub fn signed_area(&self) -> f64 {
+        let n = self.points.len();
+        let twice_area = (0..n)
+            .map(|i| {
+                let a = &self.points[i];
+                let b = &self.points[(i + 1) % n];
+                b.x * a.y - a.x * b.y
+            })
+            .sum::<f64>();
+        twice_area / 2.0
+    }
+
+    pub fn is_convex(&self) -> bool {
+        let n = self.points.len();
+        let mut orientation = 0.0;
+        let mut total_turning = 0.0;
+        for i in 0..n {
+            let a = &self.points[(i + n - 1) % n];
+            let b = &self.points[i];
+            let c = &self.points[(i + 1) % n];
+            let turn = turn(a, b, c);
+            if turn != 0.0 {
+                if orientation * turn < 0.0 {
+                    return false;
+                }
+                orientation = turn.signum();
+            }
+            let incoming = Vector2d::from_points(a, b);
+            let outgoing = Vector2d::from_points(b, c);
+            total_turning += incoming.cross(&outgoing).atan2(incoming.dot(&outgoing));
+        }
+
+        // turning the same way at every vertex is not enough: a pentagram does that too but it
+        // winds around twice
+        let winds_once = total_turning.abs() < 3.0 * std::f64::consts::PI;
+        orientation != 0.0 && winds_once
+    }
+
+    /// Whether `point` is strictly inside of the polygon, points on its boundary may go either
+    /// way
+    pub fn contains(&self, point: &Point2d) -> bool {
+        ring_contains(&self.points, point)
+    }
+
+    /// The closest pair of points, the first one on `self` and the second one on `other`. When the
+    /// polygons overlap both are the same point, common to both polygons.
+    pub fn closest_points_to(&self, other: &Polygon) -> (Point2d, Point2d) {
+        if other.contains(&self.points[0]) {
+            return (self.points[0].clone(), self.points[0].clone());
+        }
+        if self.contains(&other.points[0]) {
+            return (other.points[0].clone(), other.points[0].clone());
+        }
+
+        let mut closest = (self.points[0].clone(), other.points[0].clone());
+        let mut closest_distance = f64::INFINITY;
+        for segment in &self.segments {
+            for other_segment in &other.segments {
+                if !segment
+                    .bounds()
+                    .is_within(other_segment.bounds(), closest_distance)
+                {
+                    continue;
+                }
+                let (a, b) = segment.closest_points_to_segment(other_segment);
+                let distance = a.distance_to(&b);
+                if distance < closest_distance {
+                    closest_distance = distance;
+                    closest = (a, b);
+                }
+            }
+        }
+        closest
+    }
+
+    /// Clearance between two polygons, zero when they overlap
+    pub fn distance_to(&self, other: &Polygon) -> f64 {
+        let (a, b) = self.closest_points_to(other);
+        a.distance_to(&b)
+    }
+
+    /// Whether both polygons have the same shape up to `tolerance`, regardless of which point
+    /// they start at, of their winding and of vertices within `tolerance` of the line through
+    /// their neighbours
+    pub fn approx_eq(&self, other: &Polygon, tolerance: f64) -> bool {
+        let points = normalized(&self.points, tolerance);
+        let other_points = normalized(&other.points, tolerance);
+        if points.len() != other_points.len() {
+            return false;
+        }
+
+        let n = points.len();
+        (0..n).any(|start| {
+            (0..n).all(|i| points[i].distance_to(&other_points[(start + i) % n]) <= tolerance)
+        })
+    }
+
+    /// Whether both polygons enclose exactly the same region, i.e. [`Polygon::approx_eq`] without
+    /// any tolerance: only the start point, the winding and collinear vertices may differ
+    pub fn same_region(&self, other: &Polygon) -> bool {
+        self.approx_eq(other, 0.0)
+    }
+}
+
+/// [`Polygon::approx_eq`] for multipolygons such as the output of [`crate::clip::sum`], in any
+/// order
+pub fn multipolygons_approx_eq(polygons: &[Polygon], others: &[Polygon], tolerance: f64) -> bool {
+    if polygons.len() != others.len() {
+        return false;
+    }
+
+    // a ring may be close enough to several others, so the first one it matches is not
+    // necessarily the one to pair it with: look for a pairing of all of them, moving earlier
+    // pairs along whenever that frees a ring up
+    let is_close: Vec<Vec<bool>> = polygons
+        .iter()
+        .map(|polygon| {
+            others
+                .iter()
+                .map(|other| polygon.approx_eq(other, tolerance))
+                .collect()
+        })
+        .collect();
+    let mut matched_with = vec![None; others.len()];
+    (0..polygons.len()).all(|polygon| {
+        let mut is_visited = vec![false; others.len()];
+        pair(polygon, &is_close, &mut matched_with, &mut is_visited)
+    })
+}
+
+// Kuhn's augmenting path: pairs `polygon` with a ring it is close to, taking it away from the
+// polygon it was paired with if that one can be paired with another ring instead
+fn pair(
+    polygon: usize,
+    is_close: &[Vec<bool>],
+    matched_with: &mut [Option<usize>],
+    is_visited: &mut [bool],
+) -> bool {
+    for other in 0..matched_with.len() {
+        if !is_close[polygon][other] || is_visited[other] {
+            continue;
+        }
+        is_visited[other] = true;
+        let is_free = match matched_with[other] {
+            None => true,
+            Some(previous) => pair(previous, is_close, matched_with, is_visited),
+        };
+        if is_free {
+            matched_with[other] = Some(polygon);
+            return true;
+        }
+    }
+    false
+}
+
+/// [`Polygon::same_region`] for multipolygons, in any order
+pub fn multipolygons_same_region(polygons: &[Polygon], others: &[Polygon]) -> bool {
+    multipolygons_approx_eq(polygons, others, 0.0)
+}
+
+// wound the way Polygon expects, without repeated points nor vertices within tolerance of the
+// line through their neighbours. The vertex closest to that line goes first, ties broken by
+// position, so what is left does not depend on which vertex the ring starts at.
+fn normalized(points: &[Point2d], tolerance: f64) -> Vec<Point2d> {
+    let n = points.len();
+    let mut prev: Vec<usize> = (0..n).map(|i| (i + n - 1) % n).collect();
+    let mut next: Vec<usize> = (0..n).map(|i| (i + 1) % n).collect();
+    let mut is_kept = vec![true; n];
+
+    let redundant = |prev: usize, vertex: usize, next: usize| {
+        let deviation = if tolerance == 0.0 {
+            turn(&points[prev], &points[vertex], &points[next]).abs()
+        } else {
+            let chord = Segment::new(points[prev].clone(), points[next].clone());
+            chord.distance_to_point(&points[vertex])
+        };
+        (deviation <= tolerance).then(|| Redundant {
+            deviation,
+            point: points[vertex].clone(),
+            vertex,
+            prev,
+            next,
+        })
+    };
+    let mut candidates: BinaryHeap<Redundant> = (0..n)
+        .filter_map(|vertex| redundant(prev[vertex], vertex, next[vertex]))
+        .collect();
+
+    let mut count = n;
+    while count > 3 {
+        let Some(candidate) = candidates.pop() else {
+            break;
+        };
+        let Redundant {
+            vertex,
+            prev: before,
+            next: after,
+            ..
+        } = candidate;
+        let is_stale = !is_kept[vertex] || prev[vertex] != before || next[vertex] != after;
+        if is_stale {
+            continue;
+        }
+
+        is_kept[vertex] = false;
+        next[before] = after;
+        prev[after] = before;
+        count -= 1;
+        // either neighbour may have just become redundant
+        candidates.extend(redundant(prev[before], before, after));
+        candidates.extend(redundant(before, after, next[after]));
+    }
+
+    let mut points: Vec<Point2d> = points
+        .iter()
+        .zip(is_kept)
+        .filter(|(_, is_kept)| *is_kept)
+        .map(|(point, _)| point.clone())
+        .collect();
+    let twice_area = (0..points.len())
+        .map(|i| turn(&points[0], &points[i], &points[(i + 1) % points.len()]))
+        .sum::<f64>();
+    if twice_area < 0.0 {
+        points.reverse();
+    }
+    points
+}
+
+struct Redundant {
+    deviation: f64,
+    point: Point2d,
+    vertex: usize,
+    prev: usize,
+    next: usize,
+}
+
+// BinaryHeap is a max heap, so the smallest deviation has to compare as the greatest
+impl Ord for Redundant {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .deviation
+            .total_cmp(&self.deviation)
+            .then(other.point.x.total_cmp(&self.point.x))
+            .then(other.point.y.total_cmp(&self.point.y))
+    }
+}
+
+impl PartialOrd for Redundant {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Redundant {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Redundant {}
+
+/// Even-odd test of whether `point` is strictly inside of the ring formed by `points`, points on
+/// the boundary may go either way
+pub(crate) fn ring_contains(points: &[Point2d], point: &Point2d) -> bool {
+    let n = points.len();
+    let mut is_inside = false;
+    for i in 0..n {
+        let a = &points[i];
+        let b = &points[(i + 1) % n];
+        let straddles = (a.y > point.y) != (b.y > point.y);
+        if straddles {
+            let x = a.x + (point.y - a.y) * (b.x - a.x) / (b.y - a.y);
+            if point.x < x {
+                is_inside = !is_inside;
+            }
+        }
+    }
+    is_inside
+}
+
+/// How many times the ring formed by `points` winds around `point`, counting the way [`Polygon`]
+/// expects as positive. Points on the boundary may go either way.
+pub(crate) fn winding_number(points: &[Point2d], point: &Point2d) -> i32 {
+    let n = points.len();
+    let mut winding = 0;
+    for i in 0..n {
+        let a = &points[i];
+        let b = &points[(i + 1) % n];
+        let straddles = (a.y > point.y) != (b.y > point.y);
+        if straddles {
+            let x = a.x + (point.y - a.y) * (b.x - a.x) / (b.y - a.y);
+            if point.x < x {
+                winding += if b.y < a.y { 1 } else { -1 };
+            }
+        }
+    }
+    winding
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `c`: positive when it is wound the way
+/// [`Polygon`] expects, i.e. when `b` is a convex vertex of a well formed polygon
+pub fn turn(a: &Point2d, b: &Point2d, c: &Point2d) -> f64 {
+    let ab = Vector2d::from_points(a, b);
+    let ac = Vector2d::from_points(a, c);
+    -ab.cross(&ac)
 }
 
 pub fn polygons_from_unordered_segments(unordered_segments: Vec<Segment>) -> Vec<Polygon> {
@@ -141,7 +451,185 @@ impl Display for Polygon {
 
 #[cfg(test)]
 mod tests {
-    use crate::{point::Point2d, polygon::Polygon};
+    use crate::{
+        point::Point2d,
+        polygon::{multipolygons_approx_eq, multipolygons_same_region, normalized, Polygon},
+    };
+
+    #[test]
+    fn test_approx_eq_ignores_start_winding_collinear_points_and_drift() {
+        let p0 = Point2d::new(1.0, 1.0);
+        let p1 = Point2d::new(1.0, 3.0);
+        let p2 = Point2d::new(3.0, 3.0);
+        let p3 = Point2d::new(3.0, 1.0);
+        let points = vec![p0, p1, p2, p3];
+        let square = Polygon::from_points(points);
+
+        let p0 = Point2d::new(3.0, 3.0);
+        let p1 = Point2d::new(1.0, 3.0);
+        let p2 = Point2d::new(1.0, 2.0);
+        let p3 = Point2d::new(1.0, 1.0);
+        let p4 = Point2d::new(3.0 + 1e-15, 1.0);
+        let points = vec![p0, p1, p2, p3, p4];
+        let other_square = Polygon::from_points(points);
+
+        assert_ne!(square, other_square);
+        assert!(square.approx_eq(&other_square, 1e-12));
+        assert!(!square.same_region(&other_square));
+
+        let mut points = other_square.points.clone();
+        points[4] = Point2d::new(3.0, 1.0);
+        let other_square = Polygon::from_points(points);
+        assert!(square.same_region(&other_square));
+
+        let mut points = other_square.points.clone();
+        points[4] = Point2d::new(3.1, 1.0);
+        let other_square = Polygon::from_points(points);
+        assert!(!square.approx_eq(&other_square, 1e-12));
+    }
+
+    #[test]
+    fn test_multipolygon_comparison_ignores_order() {
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 1.0);
+        let p2 = Point2d::new(1.0, 1.0);
+        let p3 = Point2d::new(1.0, 0.0);
+        let points = vec![p0, p1, p2, p3];
+        let square = Polygon::from_points(points);
+
+        let p0 = Point2d::new(5.0, 5.0);
+        let p1 = Point2d::new(6.0, 7.0);
+        let p2 = Point2d::new(7.0, 5.0);
+        let points = vec![p0, p1, p2];
+        let triangle = Polygon::from_points(points);
+
+        let polygons = vec![square.clone(), triangle.clone()];
+        let others = vec![triangle.clone(), square.clone()];
+        assert!(multipolygons_same_region(&polygons, &others));
+        assert!(multipolygons_approx_eq(&polygons, &others, 1e-9));
+
+        let others = vec![triangle.clone(), triangle];
+        assert!(!multipolygons_same_region(&polygons, &others));
+    }
+
+    #[test]
+    fn test_multipolygon_comparison_pairs_nearly_identical_rings() {
+        let square = |dx: f64| {
+            let p0 = Point2d::new(dx, 0.0);
+            let p1 = Point2d::new(dx, 1.0);
+            let p2 = Point2d::new(dx + 1.0, 1.0);
+            let p3 = Point2d::new(dx + 1.0, 0.0);
+            Polygon::from_points(vec![p0, p1, p2, p3])
+        };
+
+        // the first square is close enough to both of the others, but the second one is only
+        // close enough to the first of them
+        let polygons = vec![square(0.075), square(0.0)];
+        let others = vec![square(0.0), square(0.15)];
+        assert!(multipolygons_approx_eq(&polygons, &others, 0.1));
+
+        let others = vec![square(0.15), square(0.0)];
+        assert!(multipolygons_approx_eq(&polygons, &others, 0.1));
+
+        let others = vec![square(0.15), square(0.16)];
+        assert!(!multipolygons_approx_eq(&polygons, &others, 0.1));
+    }
+
+    #[test]
+    fn test_normalizing_does_not_depend_on_the_start_vertex() {
+        let points = vec![
+            Point2d::new(0.0, 0.0),
+            Point2d::new(0.0, 4.0),
+            Point2d::new(4.0, 4.0),
+            Point2d::new(4.0, 0.0),
+            Point2d::new(3.0, 0.08),
+            Point2d::new(2.0, 0.12),
+            Point2d::new(1.0, 0.09),
+        ];
+        let sorted = |mut points: Vec<Point2d>| {
+            points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+            points
+        };
+
+        let expected = sorted(normalized(&points, 0.1));
+        assert!(expected.len() < points.len());
+        for start in 1..points.len() {
+            let mut rotated = points.clone();
+            rotated.rotate_left(start);
+            assert_eq!(sorted(normalized(&rotated, 0.1)), expected);
+        }
+    }
+
+    #[test]
+    fn test_distance_between_polygons() {
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 2.0);
+        let p2 = Point2d::new(2.0, 2.0);
+        let p3 = Point2d::new(2.0, 0.0);
+        let points = vec![p0, p1, p2, p3];
+        let square = Polygon::from_points(points);
+
+        let p0 = Point2d::new(5.0, 6.0);
+        let p1 = Point2d::new(6.0, 8.0);
+        let p2 = Point2d::new(7.0, 6.0);
+        let points = vec![p0, p1, p2];
+        let triangle = Polygon::from_points(points);
+
+        let (a, b) = square.closest_points_to(&triangle);
+        assert_eq!((a, b), (Point2d::new(2.0, 2.0), Point2d::new(5.0, 6.0)));
+        assert_eq!(square.distance_to(&triangle), 5.0);
+        assert_eq!(triangle.distance_to(&square), 5.0);
+
+        let p0 = Point2d::new(0.5, 0.5);
+        let p1 = Point2d::new(1.0, 1.5);
+        let p2 = Point2d::new(1.5, 0.5);
+        let points = vec![p0, p1, p2];
+        let inner_triangle = Polygon::from_points(points);
+        assert!(square.contains(&inner_triangle.points[0]));
+        assert_eq!(square.distance_to(&inner_triangle), 0.0);
+    }
+
+    #[test]
+    fn test_signed_area_is_positive_for_the_expected_winding() {
+        let p0 = Point2d::new(1.0, 1.0);
+        let p1 = Point2d::new(1.0, 3.0);
+        let p2 = Point2d::new(3.0, 3.0);
+        let p3 = Point2d::new(3.0, 1.0);
+        let mut points = vec![p0, p1, p2, p3];
+        let square = Polygon::from_points(points.clone());
+        assert_eq!(square.signed_area(), 4.0);
+
+        points.reverse();
+        let hole = Polygon::from_points(points);
+        assert_eq!(hole.signed_area(), -4.0);
+    }
+
+    #[test]
+    fn test_is_convex() {
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(3.0, 6.0);
+        let p2 = Point2d::new(6.0, 0.0);
+        let triangle = Polygon::from_points(vec![p0, p1, p2]);
+        assert!(triangle.is_convex());
+
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 2.0);
+        let p2 = Point2d::new(1.0, 2.0);
+        let p3 = Point2d::new(1.0, 1.0);
+        let p4 = Point2d::new(2.0, 1.0);
+        let p5 = Point2d::new(2.0, 0.0);
+        let l_shape = Polygon::from_points(vec![p0, p1, p2, p3, p4, p5]);
+        assert!(!l_shape.is_convex());
+
+        let pentagram = (0..5)
+            .map(|i| {
+                let angle = 4.0 * std::f64::consts::PI * i as f64 / 5.0;
+                Point2d::new(angle.cos(), angle.sin())
+            })
+            .collect();
+        let pentagram = Polygon::from_points(pentagram);
+        assert!(!pentagram.is_convex());
+    }
 
     #[test]
     fn test_equality_of_two_polygons_starting_at_different_points() {
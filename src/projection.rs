@@ -0,0 +1,327 @@
+use crate::affine::Affine2;
+use crate::point::{Point2d, Point3d};
+use crate::polygon::Polygon;
+use crate::vector::Vector3d;
+
+/// A flat polygon in 3d space, e.g. a panel or one face of an obstacle
+#[derive(Clone, Debug)]
+pub struct Polygon3d {
+    pub points: Vec<Point3d>,
+}
+
+impl Polygon3d {
+    pub fn new(points: Vec<Point3d>) -> Self {
+        let is_at_least_a_triangle = points.len() > 2;
+        debug_assert!(is_at_least_a_triangle);
+        Self { points }
+    }
+}
+
+/// A plane together with the 2d coordinate system its polygons are expressed in: `origin` maps to
+/// (0, 0), `u` is the x axis and `v` the y axis
+#[derive(Clone, Debug)]
+pub struct Plane {
+    pub origin: Point3d,
+    pub u: Vector3d,
+    pub v: Vector3d,
+}
+
+impl Plane {
+    /// `u` and `v` only need to span the plane, they are made orthonormal (keeping the direction
+    /// of `u`)
+    pub fn new(origin: Point3d, u: &Vector3d, v: &Vector3d) -> Self {
+        let u = u.normalized();
+        let v = (v - &(&u * u.dot(v))).normalized();
+        Self { origin, u, v }
+    }
+
+    pub fn normal(&self) -> Vector3d {
+        self.u.curl(&self.v)
+    }
+
+    /// Coordinates within the plane of the projection of `point` onto the plane
+    pub fn to_local(&self, point: &Point3d) -> Point2d {
+        let from_origin = Vector3d::from_points(&self.origin, point);
+        Point2d::new(from_origin.dot(&self.u), from_origin.dot(&self.v))
+    }
+
+    pub fn to_world(&self, point: &Point2d) -> Point3d {
+        let along_u = &self.u * point.x;
+        let along_v = &self.v * point.y;
+        &self.origin + &(&along_u + &along_v)
+    }
+
+    // positive in front of the plane, on the side its normal points to
+    pub(crate) fn height_of(&self, point: &Point3d) -> f64 {
+        Vector3d::from_points(&self.origin, point).dot(&self.normal())
+    }
+}
+
+/// Shadow cast onto `target` by `polygon` when lit from `sun_direction` (pointing towards the sun,
+/// need not be normalized), in the plane's own coordinates and wound the way [`Polygon`] expects.
+///
+/// Only the part of `polygon` between the sun and the plane casts a shadow. Returns `None` when
+/// nothing is left of it, when the sun grazes the plane or when the shadow has no area.
+pub fn project_shadow(
+    polygon: &Polygon3d,
+    sun_direction: &Vector3d,
+    target: &Plane,
+) -> Option<Polygon> {
+    let normal = target.normal();
+    let sun_height = sun_direction.dot(&normal);
+    if sun_height.abs() < f64::EPSILON * sun_direction.norm() {
+        return None;
+    }
+
+    let sun_side = sun_height.signum();
+    let lit_part = clip_to_front(&polygon.points, |point| sun_side * target.height_of(point));
+    if lit_part.len() < 3 {
+        return None;
+    }
+
+    // slide every point away from the sun until it reaches the plane
+    let points = lit_part
+        .iter()
+        .map(|point| {
+            let t = target.height_of(point) / sun_height;
+            let on_plane = point + &(sun_direction * -t);
+            target.to_local(&on_plane)
+        })
+        .collect::<Vec<_>>();
+
+    let mut shadow = Polygon::from_points(points);
+    if shadow.signed_area() == 0.0 {
+        return None;
+    }
+    if shadow.signed_area() < 0.0 {
+        let mut points = shadow.points;
+        points.reverse();
+        shadow = Polygon::from_points(points);
+    }
+    Some(shadow)
+}
+
+/// The map that slides points of `from` along `direction` until they reach `to`, in the
+/// coordinates of both planes; `None` when `direction` runs parallel to either plane
+pub fn projection_between(from: &Plane, to: &Plane, direction: &Vector3d) -> Option<Affine2> {
+    let epsilon = f64::EPSILON * direction.norm();
+    let along_to = direction.dot(&to.normal());
+    let along_from = direction.dot(&from.normal());
+    if along_to.abs() < epsilon || along_from.abs() < epsilon {
+        return None;
+    }
+
+    let project = |x: f64, y: f64| {
+        let point = from.to_world(&Point2d::new(x, y));
+        let t = to.height_of(&point) / along_to;
+        to.to_local(&(&point + &(direction * -t)))
+    };
+    let origin = project(0.0, 0.0);
+    let x = project(1.0, 0.0);
+    let y = project(0.0, 1.0);
+    Some(Affine2 {
+        a: x.x - origin.x,
+        b: y.x - origin.x,
+        c: origin.x,
+        d: x.y - origin.y,
+        e: y.y - origin.y,
+        f: origin.y,
+    })
+}
+
+/// Sutherland-Hodgman against a single plane: the part of the ring where `height` is not negative
+pub(crate) fn clip_to_front(points: &[Point3d], height: impl Fn(&Point3d) -> f64) -> Vec<Point3d> {
+    let n = points.len();
+    let mut clipped = Vec::with_capacity(n + 1);
+    for i in 0..n {
+        let current = &points[i];
+        let next = &points[(i + 1) % n];
+        let current_height = height(current);
+        let next_height = height(next);
+
+        if current_height >= 0.0 {
+            clipped.push(current.clone());
+        }
+        let crosses = (current_height < 0.0 && next_height > 0.0)
+            || (current_height > 0.0 && next_height < 0.0);
+        if crosses {
+            let t = current_height / (current_height - next_height);
+            let along = Vector3d::from_points(current, next);
+            clipped.push(current + &(&along * t));
+        }
+    }
+    clipped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        affine::Affine2,
+        point::{Point2d, Point3d},
+        polygon::Polygon,
+        projection::{project_shadow, projection_between, Plane, Polygon3d},
+        vector::Vector3d,
+    };
+
+    fn ground() -> Plane {
+        let origin = Point3d::new(0.0, 0.0, 0.0);
+        let x = Vector3d::from_coordinates(1.0, 0.0, 0.0);
+        let y = Vector3d::from_coordinates(0.0, 1.0, 0.0);
+        Plane::new(origin, &x, &y)
+    }
+
+    fn roof(height: f64) -> Polygon3d {
+        let p0 = Point3d::new(1.0, 1.0, height);
+        let p1 = Point3d::new(1.0, 3.0, height);
+        let p2 = Point3d::new(3.0, 3.0, height);
+        let p3 = Point3d::new(3.0, 1.0, height);
+        Polygon3d::new(vec![p0, p1, p2, p3])
+    }
+
+    #[test]
+    fn test_shadow_of_a_horizontal_square() {
+        let ground = ground();
+        let roof = roof(2.0);
+
+        let overhead = Vector3d::from_coordinates(0.0, 0.0, 1.0);
+        let shadow = project_shadow(&roof, &overhead, &ground).unwrap();
+        let p0 = Point2d::new(1.0, 1.0);
+        let p1 = Point2d::new(1.0, 3.0);
+        let p2 = Point2d::new(3.0, 3.0);
+        let p3 = Point2d::new(3.0, 1.0);
+        let points = vec![p0, p1, p2, p3];
+        assert_eq!(shadow, Polygon::from_points(points));
+
+        // sun 45 degrees above the horizon, towards +x: the shadow moves by the height along -x
+        let low_sun = Vector3d::from_coordinates(1.0, 0.0, 1.0);
+        let shadow = project_shadow(&roof, &low_sun, &ground).unwrap();
+        assert_eq!(shadow.bounds.min_x, -1.0);
+        assert_eq!(shadow.bounds.max_x, 1.0);
+        assert_eq!(shadow.signed_area(), 4.0);
+
+        let grazing_sun = Vector3d::from_coordinates(1.0, 0.0, 0.0);
+        assert!(project_shadow(&roof, &grazing_sun, &ground).is_none());
+
+        let sun_below = Vector3d::from_coordinates(0.0, 0.0, -1.0);
+        assert!(project_shadow(&roof, &sun_below, &ground).is_none());
+    }
+
+    #[test]
+    fn test_only_the_part_in_front_of_the_plane_casts_a_shadow() {
+        let ground = ground();
+        let p0 = Point3d::new(0.0, 0.0, -1.0);
+        let p1 = Point3d::new(0.0, 2.0, -1.0);
+        let p2 = Point3d::new(2.0, 2.0, 1.0);
+        let p3 = Point3d::new(2.0, 0.0, 1.0);
+        let ramp = Polygon3d::new(vec![p0, p1, p2, p3]);
+
+        let overhead = Vector3d::from_coordinates(0.0, 0.0, 1.0);
+        let shadow = project_shadow(&ramp, &overhead, &ground).unwrap();
+
+        assert_eq!(shadow.bounds.min_x, 1.0);
+        assert_eq!(shadow.signed_area(), 2.0);
+    }
+
+    #[test]
+    fn test_shadow_on_a_tilted_plane() {
+        // rising by 45 degrees towards +y, so a point (x, y, y) is at (x, y * sqrt(2)) in the plane
+        let origin = Point3d::new(0.0, 0.0, 0.0);
+        let x = Vector3d::from_coordinates(1.0, 0.0, 0.0);
+        let tilted = Vector3d::from_coordinates(0.0, 1.0, 1.0);
+        let plane = Plane::new(origin, &x, &tilted);
+        let high_roof = roof(4.0);
+
+        let overhead = Vector3d::from_coordinates(0.0, 0.0, 1.0);
+        let shadow = project_shadow(&high_roof, &overhead, &plane).unwrap();
+
+        let sqrt_2 = 2f64.sqrt();
+        assert!((shadow.bounds.min_x - 1.0).abs() < 1e-12);
+        assert!((shadow.bounds.max_x - 3.0).abs() < 1e-12);
+        assert!((shadow.bounds.min_y - sqrt_2).abs() < 1e-12);
+        assert!((shadow.bounds.max_y - 3.0 * sqrt_2).abs() < 1e-12);
+        assert!((shadow.signed_area() - 4.0 * sqrt_2).abs() < 1e-12);
+
+        // the roof at height 2 pierces the plane where y > 2, only the part below y = 2 is left
+        let shadow = project_shadow(&roof(2.0), &overhead, &plane).unwrap();
+        assert!((shadow.bounds.max_y - 2.0 * sqrt_2).abs() < 1e-12);
+        assert!((shadow.signed_area() - 2.0 * sqrt_2).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_plane_coordinates() {
+        let origin = Point3d::new(1.0, 2.0, 3.0);
+        let x = Vector3d::from_coordinates(2.0, 0.0, 0.0);
+        let skewed = Vector3d::from_coordinates(1.0, 1.0, 1.0);
+        let plane = Plane::new(origin.clone(), &x, &skewed);
+
+        // v is made perpendicular to u, and both have unit length
+        let v = Vector3d::from_coordinates(0.0, 1.0, 1.0).normalized();
+        assert!((plane.u.dot(&x) - 2.0).abs() < 1e-12);
+        assert!((plane.v.dot(&v) - 1.0).abs() < 1e-12);
+        assert!((plane.normal().norm() - 1.0).abs() < 1e-12);
+
+        let point = Point2d::new(0.5, 2.0);
+        let world = plane.to_world(&point);
+        assert!((plane.height_of(&world)).abs() < 1e-12);
+        assert!(plane.to_local(&world).distance_to(&point) < 1e-12);
+
+        // points off the plane are projected onto it along the normal
+        let above = &world + &plane.normal();
+        assert!((plane.height_of(&above) - 1.0).abs() < 1e-12);
+        assert!(plane.to_local(&above).distance_to(&point) < 1e-12);
+        assert!(plane.to_local(&origin).distance_to(&Point2d::new(0.0, 0.0)) < 1e-12);
+    }
+
+    #[test]
+    fn test_projection_between_planes() {
+        // a panel tilted by 45 degrees towards +y, one unit above the ground
+        let origin = Point3d::new(0.0, 0.0, 1.0);
+        let x = Vector3d::from_coordinates(1.0, 0.0, 0.0);
+        let tilted = Vector3d::from_coordinates(0.0, 1.0, 1.0);
+        let panel = Plane::new(origin, &x, &tilted);
+        let ground = ground();
+
+        // (a, b) on the panel is (a, b / sqrt(2), 1 + b / sqrt(2)), which the sun towards +x at
+        // 45 degrees moves by its height along -x
+        let sun = Vector3d::from_coordinates(1.0, 0.0, 1.0);
+        let affine = projection_between(&panel, &ground, &sun).unwrap();
+        let half_sqrt_2 = 0.5 * 2f64.sqrt();
+        let expected = Affine2 {
+            a: 1.0,
+            b: -half_sqrt_2,
+            c: -1.0,
+            d: 0.0,
+            e: half_sqrt_2,
+            f: 0.0,
+        };
+        for (actual, expected) in [
+            (affine.a, expected.a),
+            (affine.b, expected.b),
+            (affine.c, expected.c),
+            (affine.d, expected.d),
+            (affine.e, expected.e),
+            (affine.f, expected.f),
+        ] {
+            assert!((actual - expected).abs() < 1e-12);
+        }
+
+        // the same as projecting the points themselves
+        let point = Point2d::new(2.0, 3.0);
+        let world = panel.to_world(&point);
+        let triangle = Polygon3d::new(vec![
+            world.clone(),
+            &world + &Vector3d::from_coordinates(0.0, 0.5, 0.0),
+            &world + &Vector3d::from_coordinates(0.5, 0.0, 0.0),
+        ]);
+        let projected = project_shadow(&triangle, &sun, &ground).unwrap();
+        let moved = affine.apply(&point);
+        assert!(projected
+            .iter_points()
+            .any(|p| p.distance_to(&moved) < 1e-12));
+
+        let along_the_ground = Vector3d::from_coordinates(1.0, 0.0, 0.0);
+        assert!(projection_between(&panel, &ground, &along_the_ground).is_none());
+        let along_the_panel = Vector3d::from_coordinates(0.0, 1.0, 1.0);
+        assert!(projection_between(&panel, &ground, &along_the_panel).is_none());
+    }
+}
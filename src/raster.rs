@@ -0,0 +1,264 @@
+use crate::clip;
+use crate::point::Point2d;
+use crate::polygon::Polygon;
+
+/// Which points a set of rings covers, given how many times the rings wind around them: every
+/// ring counts +1 one way and -1 the other, so that holes cancel out the outline around them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FillRule {
+    /// Inside of an odd number of rings
+    EvenOdd,
+    /// Wound around at all, overlapping polygons fill each other's gaps
+    NonZero,
+}
+
+/// One bit per cell of a [`Grid`], row by row
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mask {
+    pub columns: usize,
+    pub rows: usize,
+    pub cells: Vec<bool>,
+}
+
+impl Mask {
+    pub fn get(&self, row: usize, column: usize) -> bool {
+        self.cells[row * self.columns + column]
+    }
+
+    /// How many cells are set
+    pub fn count(&self) -> usize {
+        self.cells.iter().filter(|cell| **cell).count()
+    }
+}
+
+/// Square cells, `rows` of `columns` each, starting at `origin` (the corner of the first cell
+/// with the smallest coordinates) and going towards increasing x and y
+#[derive(Clone, Debug)]
+pub struct Grid {
+    pub origin: Point2d,
+    pub cell_size: f64,
+    pub columns: usize,
+    pub rows: usize,
+}
+
+impl Grid {
+    pub fn cell(&self, row: usize, column: usize) -> Polygon {
+        let min_x = self.origin.x + column as f64 * self.cell_size;
+        let min_y = self.origin.y + row as f64 * self.cell_size;
+        rectangle(min_x, min_y, min_x + self.cell_size, min_y + self.cell_size)
+    }
+
+    /// Exact share of every cell covered by `polygons`, row by row: the area of the polygons
+    /// within each cell over the area of the cell, rather than whether its center is inside.
+    ///
+    /// Holes, wound the other way around as usual, take their area away; the polygons shouldn't
+    /// overlap each other, as in the output of [`clip::sum`].
+    pub fn coverage(&self, polygons: &[Polygon]) -> Vec<f64> {
+        let mut coverage = vec![0.0; self.rows * self.columns];
+        let cell_area = self.cell_size * self.cell_size;
+        for polygon in polygons {
+            let Some((rows, columns)) = self.span(polygon) else {
+                continue;
+            };
+
+            for row in rows {
+                let min_y = self.origin.y + row as f64 * self.cell_size;
+                let max_x = self.origin.x + self.columns as f64 * self.cell_size;
+                let strip = rectangle(self.origin.x, min_y, max_x, min_y + self.cell_size);
+                let Some(in_strip) = clip::intersect_convex(polygon, &strip) else {
+                    continue;
+                };
+
+                for column in columns.clone() {
+                    let cell = self.cell(row, column);
+                    if let Some(in_cell) = clip::intersect_convex(&in_strip, &cell) {
+                        coverage[row * self.columns + column] += in_cell.signed_area() / cell_area;
+                    }
+                }
+            }
+        }
+
+        for share in coverage.iter_mut() {
+            *share = share.clamp(0.0, 1.0);
+        }
+        coverage
+    }
+
+    /// Cells whose center is covered by `polygons` under `rule`, filled a row at a time between
+    /// the crossings of the polygons with the line through the centers of the row. Unlike
+    /// [`Grid::coverage`], the polygons may overlap each other.
+    pub fn mask(&self, polygons: &[Polygon], rule: FillRule) -> Mask {
+        let mut cells = vec![false; self.rows * self.columns];
+        let mut crossings = Vec::new();
+        for row in 0..self.rows {
+            let y = self.origin.y + (row as f64 + 0.5) * self.cell_size;
+            crossings.clear();
+            for polygon in polygons {
+                if y < polygon.bounds.min_y || y > polygon.bounds.max_y {
+                    continue;
+                }
+                // half open so that a vertex on the line is crossed once, or not at all
+                for segment in polygon.segments.iter() {
+                    let (start, end) = (&segment.start, &segment.end);
+                    if (start.y <= y) == (end.y <= y) {
+                        continue;
+                    }
+                    let t = (y - start.y) / (end.y - start.y);
+                    let x = start.x + t * (end.x - start.x);
+                    let winding = if end.y > start.y { 1 } else { -1 };
+                    crossings.push((x, winding));
+                }
+            }
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let mut winding = 0;
+            for pair in crossings.windows(2) {
+                winding += pair[0].1;
+                let inside = match rule {
+                    FillRule::EvenOdd => winding % 2 != 0,
+                    FillRule::NonZero => winding != 0,
+                };
+                if !inside {
+                    continue;
+                }
+                // the columns whose center is in between
+                let to_column = |x: f64| {
+                    let column = ((x - self.origin.x) / self.cell_size - 0.5).ceil();
+                    column.clamp(0.0, self.columns as f64) as usize
+                };
+                let start = row * self.columns;
+                cells[start + to_column(pair[0].0)..start + to_column(pair[1].0)].fill(true);
+            }
+        }
+
+        Mask {
+            columns: self.columns,
+            rows: self.rows,
+            cells,
+        }
+    }
+
+    // the rows and columns of the cells the bounds of `polygon` overlap, if any
+    fn span(&self, polygon: &Polygon) -> Option<(std::ops::Range<usize>, std::ops::Range<usize>)> {
+        let bounds = &polygon.bounds;
+        let to_cells = |min: f64, max: f64, origin: f64, count: usize| {
+            let first = ((min - origin) / self.cell_size).floor().max(0.0) as usize;
+            let last = ((max - origin) / self.cell_size).ceil().min(count as f64);
+            if last <= 0.0 || first >= count {
+                return None;
+            }
+            Some(first..last as usize)
+        };
+        let rows = to_cells(bounds.min_y, bounds.max_y, self.origin.y, self.rows)?;
+        let columns = to_cells(bounds.min_x, bounds.max_x, self.origin.x, self.columns)?;
+        Some((rows, columns))
+    }
+}
+
+fn rectangle(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Polygon {
+    let p0 = Point2d::new(min_x, min_y);
+    let p1 = Point2d::new(min_x, max_y);
+    let p2 = Point2d::new(max_x, max_y);
+    let p3 = Point2d::new(max_x, min_y);
+    Polygon::from_points(vec![p0, p1, p2, p3])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        clip,
+        point::Point2d,
+        polygon::Polygon,
+        raster::{rectangle, FillRule, Grid},
+    };
+
+    fn grid(cell_size: f64, size: usize) -> Grid {
+        Grid {
+            origin: Point2d::new(0.0, 0.0),
+            cell_size,
+            columns: size,
+            rows: size,
+        }
+    }
+
+    #[test]
+    fn test_coverage_of_a_square_across_cells() {
+        let square = rectangle(0.5, 0.5, 1.5, 1.5);
+        let coverage = grid(1.0, 2).coverage(&[square]);
+        assert_eq!(coverage, vec![0.25, 0.25, 0.25, 0.25]);
+
+        // a ring whose hole takes half of the second row away
+        let outer = rectangle(0.0, 0.0, 2.0, 2.0);
+        let mut hole = rectangle(0.0, 1.0, 2.0, 1.5).points;
+        hole.reverse();
+        let coverage = grid(1.0, 2).coverage(&[outer, Polygon::from_points(hole)]);
+        assert_eq!(coverage, vec![1.0, 1.0, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn test_coverage_adds_up_to_the_area() {
+        let p0 = Point2d::new(0.3, 0.2);
+        let p1 = Point2d::new(1.7, 3.9);
+        let p2 = Point2d::new(2.2, 1.1);
+        let p3 = Point2d::new(3.6, 3.3);
+        let p4 = Point2d::new(3.1, 0.4);
+        let zigzag = [Polygon::from_points(vec![p0, p1, p2, p3, p4])];
+        let expected = zigzag[0].signed_area();
+        assert!(expected > 0.0);
+
+        for (cell_size, size) in [(1.0, 4), (0.25, 16), (0.1, 40)] {
+            let coverage = grid(cell_size, size).coverage(&zigzag);
+            let area = coverage.iter().sum::<f64>() * cell_size * cell_size;
+            assert!((area - expected).abs() < 1e-9, "{cell_size}");
+            assert!(coverage.iter().all(|share| (0.0..=1.0).contains(share)));
+        }
+
+        // only what falls within the grid is counted
+        let coverage = grid(1.0, 2).coverage(&[rectangle(-1.0, -1.0, 1.0, 1.0)]);
+        assert_eq!(coverage, vec![1.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_mask_fill_rules() {
+        let grid = grid(1.0, 4);
+        let outer = rectangle(0.0, 0.0, 4.0, 4.0);
+        let mut hole = rectangle(1.0, 1.0, 3.0, 3.0).points;
+        hole.reverse();
+        let ring = [outer.clone(), Polygon::from_points(hole)];
+        for rule in [FillRule::EvenOdd, FillRule::NonZero] {
+            let mask = grid.mask(&ring, rule);
+            assert_eq!(mask.count(), 12);
+            assert!(mask.get(0, 0) && !mask.get(1, 1) && !mask.get(2, 2) && mask.get(3, 2));
+        }
+
+        // the same square twice: wound around twice, which is even
+        let twice = [outer.clone(), outer];
+        assert_eq!(grid.mask(&twice, FillRule::EvenOdd).count(), 0);
+        assert_eq!(grid.mask(&twice, FillRule::NonZero).count(), 16);
+
+        // cells are in when their center is
+        let grid = Grid {
+            origin: Point2d::new(-1.0, 10.0),
+            cell_size: 0.5,
+            columns: 8,
+            rows: 2,
+        };
+        let mask = grid.mask(&[rectangle(0.3, 10.0, 1.3, 10.4)], FillRule::NonZero);
+        assert_eq!(mask.count(), 2);
+        assert!(mask.get(0, 3) && mask.get(0, 4));
+    }
+
+    #[test]
+    fn test_mask_of_the_sum_matches_the_non_zero_mask_of_the_parts() {
+        let a = rectangle(1.0, 1.0, 3.0, 3.0);
+        let b = rectangle(2.0, 0.0, 4.0, 2.0);
+        let c = rectangle(0.5, 2.5, 1.5, 3.5);
+        let polygons = vec![a, b, c];
+        let grid = grid(0.1, 50);
+
+        let expected = grid.mask(&polygons, FillRule::NonZero);
+        let sum = clip::sum(polygons);
+        assert_eq!(grid.mask(&sum, FillRule::EvenOdd), expected);
+        assert_eq!(grid.mask(&sum, FillRule::NonZero), expected);
+    }
+}
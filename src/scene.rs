@@ -0,0 +1,465 @@
+use crate::clip;
+use crate::obstacle::Obstacle;
+use crate::point::{Point2d, Point3d};
+use crate::polygon::Polygon;
+use crate::projection::{project_shadow, projection_between, Plane, Polygon3d};
+use crate::shading::{PanelFootprint, PanelShading};
+use crate::vector::Vector3d;
+
+/// A PV module: a flat rectangle `width` wide along its row and `height` long up its slope, tilted
+/// by `tilt` degrees from the horizontal and facing `azimuth` degrees clockwise from north, in site
+/// coordinates (x east, y north and z up, as in [`crate::solar`]). A negative tilt turns the panel
+/// the other way about its row, towards the opposite azimuth.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Panel {
+    pub center: Point3d,
+    pub width: f64,
+    pub height: f64,
+    pub tilt: f64,
+    pub azimuth: f64,
+    pub row: usize,
+}
+
+impl Panel {
+    /// The panel's own plane: the origin is its center, x runs along the row (to the right when
+    /// looking at its face) and y up the slope
+    pub fn plane(&self) -> Plane {
+        let (sin_azimuth, cos_azimuth) = self.azimuth.to_radians().sin_cos();
+        let (sin_tilt, cos_tilt) = self.tilt.to_radians().sin_cos();
+        let along_row = Vector3d::from_coordinates(-cos_azimuth, sin_azimuth, 0.0);
+        let up_slope =
+            Vector3d::from_coordinates(-sin_azimuth * cos_tilt, -cos_azimuth * cos_tilt, sin_tilt);
+        Plane::new(self.center.clone(), &along_row, &up_slope)
+    }
+
+    /// The rectangle in the panel's plane, see [`Panel::plane`]
+    pub fn outline(&self) -> Polygon {
+        let half_width = self.width / 2.0;
+        let half_height = self.height / 2.0;
+        let p0 = Point2d::new(-half_width, -half_height);
+        let p1 = Point2d::new(-half_width, half_height);
+        let p2 = Point2d::new(half_width, half_height);
+        let p3 = Point2d::new(half_width, -half_height);
+        Polygon::from_points(vec![p0, p1, p2, p3])
+    }
+
+    pub fn corners(&self) -> Polygon3d {
+        let plane = self.plane();
+        let points = self
+            .outline()
+            .iter_points()
+            .map(|point| plane.to_world(point))
+            .collect();
+        Polygon3d::new(points)
+    }
+
+    /// Whether the sun shines on the panel's face rather than on its back
+    pub fn faces(&self, sun_direction: &Vector3d) -> bool {
+        sun_direction.dot(&self.plane().normal()) > 0.0
+    }
+}
+
+/// Rows of identical fixed-tilt panels. Rows are `pitch` apart and the first row is the one in
+/// front, the one that the panels face; `clearance` is the height of the lower edge of the panels
+/// above the ground and `gap` the space between neighbours in a row.
+#[derive(Clone, Debug)]
+pub struct FixedTiltLayout {
+    pub rows: usize,
+    pub panels_per_row: usize,
+    pub panel_width: f64,
+    pub panel_height: f64,
+    pub tilt: f64,
+    pub azimuth: f64,
+    pub pitch: f64,
+    pub clearance: f64,
+    pub gap: f64,
+}
+
+/// Everything that can cast a shadow onto the panels
+#[derive(Clone, Debug, Default)]
+pub struct Scene {
+    pub panels: Vec<Panel>,
+    pub obstacles: Vec<Obstacle>,
+}
+
+impl Scene {
+    pub fn new(panels: Vec<Panel>) -> Self {
+        Self {
+            panels,
+            obstacles: Vec::new(),
+        }
+    }
+
+    pub fn with_obstacles(mut self, obstacles: Vec<Obstacle>) -> Self {
+        self.obstacles = obstacles;
+        self
+    }
+
+    /// A layout centered on the origin horizontally
+    pub fn fixed_tilt(layout: &FixedTiltLayout) -> Self {
+        let (sin_azimuth, cos_azimuth) = layout.azimuth.to_radians().sin_cos();
+        let center_height =
+            layout.clearance + layout.panel_height / 2.0 * layout.tilt.to_radians().sin();
+        let first_row = (layout.rows as f64 - 1.0) / 2.0;
+        let first_panel = (layout.panels_per_row as f64 - 1.0) / 2.0;
+        let spacing = layout.panel_width + layout.gap;
+
+        let mut panels = Vec::with_capacity(layout.rows * layout.panels_per_row);
+        for row in 0..layout.rows {
+            // rows go backwards, away from the direction the panels face
+            let back = (row as f64 - first_row) * layout.pitch;
+            for i in 0..layout.panels_per_row {
+                let right = (i as f64 - first_panel) * spacing;
+                let x = -sin_azimuth * back - cos_azimuth * right;
+                let y = -cos_azimuth * back + sin_azimuth * right;
+                panels.push(Panel {
+                    center: Point3d::new(x, y, center_height),
+                    width: layout.panel_width,
+                    height: layout.panel_height,
+                    tilt: layout.tilt,
+                    azimuth: layout.azimuth,
+                    row,
+                });
+            }
+        }
+        Self::new(panels)
+    }
+
+    /// Shadows cast by the other panels and by the obstacles onto panel `index`, in that panel's
+    /// plane (see [`Panel::plane`]) and ready for [`crate::clip::sum`]. Shadows that miss the panel
+    /// are left out, and so are panels lying in the same plane, such as its neighbours in a row,
+    /// which can't cast any; whether the sun reaches the panel's face at all is up to
+    /// [`Panel::faces`].
+    pub fn shadows_on(&self, index: usize, sun_direction: &Vector3d) -> Vec<Polygon> {
+        self.shadows_from(index, 0..self.panels.len(), sun_direction)
+    }
+
+    // shadows_on, with only the panels in `casters` casting shadows
+    fn shadows_from(
+        &self,
+        index: usize,
+        casters: impl IntoIterator<Item = usize>,
+        sun_direction: &Vector3d,
+    ) -> Vec<Polygon> {
+        let panel = &self.panels[index];
+        let plane = panel.plane();
+        let outline = panel.outline();
+        let tolerance = 1e-9 * (panel.width + panel.height);
+        let panel_shadows = casters
+            .into_iter()
+            .filter(|&other_index| other_index != index)
+            .map(|other_index| self.panels[other_index].corners())
+            .filter(|corners| {
+                let is_coplanar = corners
+                    .points
+                    .iter()
+                    .all(|corner| plane.height_of(corner).abs() <= tolerance);
+                !is_coplanar
+            })
+            .filter_map(|corners| project_shadow(&corners, sun_direction, &plane));
+        let obstacle_shadows = self
+            .obstacles
+            .iter()
+            .flat_map(|obstacle| obstacle.shadows(sun_direction, &plane));
+        panel_shadows
+            .chain(obstacle_shadows)
+            .filter(|shadow| shadow.bounds.is_within(&outline.bounds, 0.0))
+            .collect()
+    }
+
+    /// [`Scene::shadows_on`] for every panel
+    pub fn shadows(&self, sun_direction: &Vector3d) -> Vec<Vec<Polygon>> {
+        (0..self.panels.len())
+            .map(|index| self.shadows_on(index, sun_direction))
+            .collect()
+    }
+
+    /// Where the panels fall onto `plane` when seen from the sun, to apply a shadow merged in that
+    /// plane with [`crate::shading::report`]. `None` for panels the sun doesn't put onto it.
+    pub fn footprints_on(
+        &self,
+        plane: &Plane,
+        sun_direction: &Vector3d,
+    ) -> Vec<Option<PanelFootprint>> {
+        self.panels
+            .iter()
+            .map(|panel| {
+                let outline = project_shadow(&panel.corners(), sun_direction, plane)?;
+                let to_local = projection_between(plane, &panel.plane(), sun_direction)?;
+                Some(PanelFootprint { outline, to_local })
+            })
+            .collect()
+    }
+
+    /// How much of every panel is in the shade of the others, merging the shadows on each panel
+    /// with [`clip::sum`]. Panels the sun shines on from behind are fully shaded.
+    ///
+    /// As in [`crate::shading::report`], every panel is projected only once, onto a plane behind
+    /// all of them: a panel can only shade another one where their footprints there overlap, so
+    /// each panel only merges the shadows of the few panels around it.
+    pub fn shading(&self, sun_direction: &Vector3d) -> Vec<PanelShading> {
+        let casters = self.casters(sun_direction);
+        self.panels
+            .iter()
+            .enumerate()
+            .map(|(index, panel)| {
+                let outline = panel.outline();
+                if !panel.faces(sun_direction) {
+                    return PanelShading::fully_shaded(&outline);
+                }
+                let shadows = match &casters {
+                    Some(casters) => {
+                        self.shadows_from(index, casters[index].iter().copied(), sun_direction)
+                    }
+                    None => self.shadows_on(index, sun_direction),
+                };
+                PanelShading::of(&PanelFootprint::local(outline), &clip::sum(shadows))
+            })
+            .collect()
+    }
+
+    // for every panel, the other panels whose footprints overlap its own, on a plane parallel to
+    // the first panel and behind all of them as seen from the sun, found by sweeping the
+    // footprints from left to right; None when the sun runs along that plane
+    fn casters(&self, sun_direction: &Vector3d) -> Option<Vec<Vec<usize>>> {
+        let Some(first) = self.panels.first() else {
+            return Some(Vec::new());
+        };
+        let plane = first.plane();
+        let normal = plane.normal();
+        let sun_side = sun_direction.dot(&normal).signum();
+        let nearest = self
+            .panels
+            .iter()
+            .flat_map(|panel| panel.corners().points)
+            .map(|corner| sun_side * plane.height_of(&corner))
+            .fold(f64::INFINITY, f64::min);
+        let origin = &plane.origin + &(&normal * (sun_side * (nearest - 1.0)));
+        let behind = Plane::new(origin, &plane.u, &plane.v);
+
+        let bounds = self
+            .panels
+            .iter()
+            .map(|panel| {
+                project_shadow(&panel.corners(), sun_direction, &behind)
+                    .map(|footprint| footprint.bounds)
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let mut order = (0..bounds.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| bounds[a].min_x.total_cmp(&bounds[b].min_x));
+        let mut casters = vec![Vec::new(); bounds.len()];
+        for (k, &i) in order.iter().enumerate() {
+            for &j in order[k + 1..].iter() {
+                if bounds[j].min_x > bounds[i].max_x {
+                    break;
+                }
+                if bounds[i].is_within(&bounds[j], 0.0) {
+                    casters[i].push(j);
+                    casters[j].push(i);
+                }
+            }
+        }
+        for casters in casters.iter_mut() {
+            casters.sort_unstable();
+        }
+        Some(casters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        affine::Transform,
+        clip,
+        obstacle::Obstacle,
+        point::Point3d,
+        projection::Plane,
+        scene::{FixedTiltLayout, Panel, Scene},
+        vector::Vector3d,
+    };
+
+    fn layout() -> FixedTiltLayout {
+        FixedTiltLayout {
+            rows: 2,
+            panels_per_row: 3,
+            panel_width: 1.0,
+            panel_height: 2.0,
+            tilt: 30.0,
+            azimuth: 180.0,
+            pitch: 3.0,
+            clearance: 0.5,
+            gap: 0.1,
+        }
+    }
+
+    #[test]
+    fn test_panel_geometry() {
+        let panel = Panel {
+            center: Point3d::new(0.0, 0.0, 1.0),
+            width: 1.0,
+            height: 2.0,
+            tilt: 90.0,
+            azimuth: 180.0,
+            row: 0,
+        };
+
+        // a vertical panel facing south
+        let normal = panel.plane().normal();
+        assert!((normal.y + 1.0).abs() < 1e-12);
+        assert!(panel.faces(&Vector3d::from_coordinates(0.0, -1.0, 1.0)));
+        assert!(!panel.faces(&Vector3d::from_coordinates(0.0, 1.0, 1.0)));
+
+        let corners = panel.corners();
+        let top = corners
+            .points
+            .iter()
+            .map(|point| point.z)
+            .fold(0.0, f64::max);
+        assert!((top - 2.0).abs() < 1e-12);
+        assert_eq!(panel.outline().signed_area(), 2.0);
+
+        // seen from above on the ground, the panel is a 1 by 1 rectangle
+        let origin = Point3d::new(0.0, 0.0, 0.0);
+        let x = Vector3d::from_coordinates(1.0, 0.0, 0.0);
+        let y = Vector3d::from_coordinates(0.0, 1.0, 0.0);
+        let ground = Plane::new(origin, &x, &y);
+        let tilted = Panel {
+            tilt: 60.0,
+            ..panel
+        };
+        let sun = Vector3d::from_coordinates(0.0, 0.0, 1.0);
+        let footprint = Scene::new(vec![tilted]).footprints_on(&ground, &sun)[0]
+            .clone()
+            .unwrap();
+        assert!((footprint.outline.signed_area() - 1.0).abs() < 1e-12);
+        let local = footprint.outline.transform(&footprint.to_local);
+        assert!((local.signed_area() - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_fixed_tilt_layout() {
+        let scene = Scene::fixed_tilt(&layout());
+
+        assert_eq!(scene.panels.len(), 6);
+        // facing south, so the second row is further north
+        assert!(scene.panels[3].center.y > scene.panels[0].center.y);
+        assert!((scene.panels[3].center.y - scene.panels[0].center.y - 3.0).abs() < 1e-12);
+        assert!((scene.panels[1].center.x - scene.panels[0].center.x - 1.1).abs() < 1e-12);
+        let lowest = scene.panels[0]
+            .corners()
+            .points
+            .iter()
+            .map(|point| point.z)
+            .fold(f64::INFINITY, f64::min);
+        assert!((lowest - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_front_row_shades_the_back_row_when_the_sun_is_low() {
+        let scene = Scene::fixed_tilt(&layout());
+        // from the south, 10 degrees above the horizon
+        let elevation = 10f64.to_radians();
+        let sun = Vector3d::from_coordinates(0.0, -elevation.cos(), elevation.sin());
+
+        let shadows = scene.shadows(&sun);
+        assert!(shadows[..3].iter().all(|shadows| shadows.is_empty()));
+
+        let middle_back = clip::sum(shadows[4].clone());
+        let outline = scene.panels[4].outline();
+        let shaded_bottom = middle_back
+            .iter()
+            .any(|shadow| shadow.bounds.min_y <= outline.bounds.min_y);
+        assert!(shaded_bottom);
+
+        let shading = scene.shading(&sun);
+        assert_eq!(shading[0].shaded_fraction, 0.0);
+        assert!(shading[4].shaded_fraction > 0.0 && shading[4].shaded_fraction < 1.0);
+        let area = shading[4].shaded_fraction * outline.signed_area();
+        assert!((shading[4].shaded_area - area).abs() < 1e-9);
+
+        // the panels face south, away from a sun in the north
+        let behind = Vector3d::from_coordinates(0.0, 1.0, 0.2);
+        assert!(scene
+            .shading(&behind)
+            .iter()
+            .all(|shading| shading.shaded_fraction == 1.0));
+
+        // high noon in summer: nothing is shaded
+        let elevation = 70f64.to_radians();
+        let sun = Vector3d::from_coordinates(0.0, -elevation.cos(), elevation.sin());
+        let shadows = scene.shadows(&sun);
+        assert!(shadows.iter().all(|shadows| shadows.is_empty()));
+
+        // unless a pole stands right in front of the first panel
+        let pole = Obstacle::pole(&Point3d::new(-1.1, -2.0, 0.0), 0.1, 4.0);
+        let scene = scene.with_obstacles(vec![pole]);
+        let shadows = scene.shadows(&sun);
+        assert_eq!(shadows[0].len(), 1);
+        assert!(shadows[1..].iter().all(|shadows| shadows.is_empty()));
+    }
+
+    #[test]
+    fn test_touching_panels_in_a_row_do_not_shade_each_other() {
+        let layout = FixedTiltLayout {
+            rows: 1,
+            gap: 0.0,
+            ..layout()
+        };
+        let scene = Scene::fixed_tilt(&layout);
+
+        // low in the south-east and south-west, and grazing the panels along their row
+        let elevation = 10f64.to_radians();
+        let suns = [
+            Vector3d::from_coordinates(elevation.cos(), -elevation.cos(), elevation.sin()),
+            Vector3d::from_coordinates(-elevation.cos(), -elevation.cos(), elevation.sin()),
+            Vector3d::from_coordinates(1.0, -0.2, 0.1),
+        ];
+        for sun in &suns {
+            let shadows = scene.shadows(sun);
+            assert!(shadows.iter().all(|shadows| shadows.is_empty()));
+            assert!(scene
+                .shading(sun)
+                .iter()
+                .all(|shading| shading.shaded_fraction == 0.0));
+        }
+
+        // a panel stacked on top of another one doesn't shade it either
+        let stacked = Scene::new(vec![scene.panels[0].clone(), scene.panels[0].clone()]);
+        assert!(stacked
+            .shadows(&suns[0])
+            .iter()
+            .all(|shadows| shadows.is_empty()));
+    }
+
+    #[test]
+    fn test_shading_only_looks_at_panels_whose_footprints_overlap() {
+        let layout = FixedTiltLayout {
+            rows: 4,
+            panels_per_row: 5,
+            ..layout()
+        };
+        let scene = Scene::fixed_tilt(&layout);
+        // low in the south-east
+        let elevation = 12f64.to_radians();
+        let (sin, cos) = elevation.sin_cos();
+        let sun = Vector3d::from_coordinates(0.6 * cos, -0.8 * cos, sin);
+
+        // a panel's footprint only overlaps those of its neighbours in the rows in front and
+        // behind, and the ones behind cast no shadow onto it
+        let casters = scene.casters(&sun).unwrap();
+        assert!(casters.iter().all(|casters| casters.len() <= 4));
+        for (index, casters) in casters.iter().enumerate() {
+            let shadows = scene.shadows_from(index, casters.iter().copied(), &sun);
+            assert_eq!(shadows, scene.shadows_on(index, &sun));
+        }
+
+        let shading = scene.shading(&sun);
+        assert!(shading[..5]
+            .iter()
+            .all(|shading| shading.shaded_fraction == 0.0));
+        assert!(shading[5..]
+            .iter()
+            .any(|shading| shading.shaded_fraction > 0.0));
+    }
+}
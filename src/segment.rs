@@ -1,5 +1,6 @@
 use crate::bounds::Bounds;
 use crate::point::Point2d;
+use crate::polygon::turn;
 use crate::vector::{Vector2d, Vector3d};
 use std::cmp::Ordering::*;
 use std::fmt::Display;
@@ -174,6 +175,98 @@ impl Segment {
         let slope = (point.y - self.start.y) / (point.x - self.start.x);
         self.boxes(point) && (point == &self.start || slope == self.slope)
     }
+
+    pub fn bounds(&self) -> &Bounds {
+        &self.bounds
+    }
+
+    pub fn length(&self) -> f64 {
+        self.start.distance_to(&self.end)
+    }
+
+    /// The point of the segment nearest to `point`
+    pub fn closest_point_to(&self, point: &Point2d) -> Point2d {
+        let along: Vector2d = self.into();
+        let length_sq = along.norm_sq();
+        if length_sq == 0.0 {
+            return self.start.clone();
+        }
+        let to_point = Vector2d::from_points(&self.start, point);
+        let t = (to_point.dot(&along) / length_sq).clamp(0.0, 1.0);
+        self.at(t)
+    }
+
+    pub fn distance_to_point(&self, point: &Point2d) -> f64 {
+        self.closest_point_to(point).distance_to(point)
+    }
+
+    /// Whether the segments share at least one point, including touching and overlapping
+    pub fn intersects(&self, other: &Segment) -> bool {
+        let d1 = turn(&self.start, &self.end, &other.start);
+        let d2 = turn(&self.start, &self.end, &other.end);
+        let d3 = turn(&other.start, &other.end, &self.start);
+        let d4 = turn(&other.start, &other.end, &self.end);
+        if d1 * d2 < 0.0 && d3 * d4 < 0.0 {
+            return true;
+        }
+
+        (d1 == 0.0 && self.boxes(&other.start))
+            || (d2 == 0.0 && self.boxes(&other.end))
+            || (d3 == 0.0 && other.boxes(&self.start))
+            || (d4 == 0.0 && other.boxes(&self.end))
+    }
+
+    /// The closest pair of points, the first one on `self` and the second one on `other`. Both
+    /// are the same point when the segments intersect.
+    pub fn closest_points_to_segment(&self, other: &Segment) -> (Point2d, Point2d) {
+        if self.intersects(other) {
+            let point = self.intersection_point(other);
+            return (point.clone(), point);
+        }
+
+        // without an intersection one of the closest points is always an endpoint
+        let candidates = [
+            (self.start.clone(), other.closest_point_to(&self.start)),
+            (self.end.clone(), other.closest_point_to(&self.end)),
+            (self.closest_point_to(&other.start), other.start.clone()),
+            (self.closest_point_to(&other.end), other.end.clone()),
+        ];
+        candidates
+            .into_iter()
+            .min_by(|(a, b), (c, d)| a.distance_to(b).partial_cmp(&c.distance_to(d)).unwrap())
+            .unwrap()
+    }
+
+    pub fn distance_to_segment(&self, other: &Segment) -> f64 {
+        let (a, b) = self.closest_points_to_segment(other);
+        a.distance_to(&b)
+    }
+
+    // a point both segments share, they must intersect
+    pub(crate) fn intersection_point(&self, other: &Segment) -> Point2d {
+        let along: Vector2d = self.into();
+        let other_along: Vector2d = other.into();
+        let denominator = along.cross(&other_along);
+        if denominator == 0.0 {
+            // collinear and overlapping
+            return [&other.start, &other.end, &self.start, &self.end]
+                .into_iter()
+                .find(|&point| self.boxes(point) && other.boxes(point))
+                .unwrap()
+                .clone();
+        }
+        let to_other = Vector2d::from_points(&self.start, &other.start);
+        let t = to_other.cross(&other_along) / denominator;
+        self.at(t.clamp(0.0, 1.0))
+    }
+
+    // the point at t * length from start
+    pub(crate) fn at(&self, t: f64) -> Point2d {
+        Point2d::new(
+            self.start.x + t * (self.end.x - self.start.x),
+            self.start.y + t * (self.end.y - self.start.y),
+        )
+    }
 }
 
 impl Display for Segment {
@@ -189,3 +282,39 @@ fn min_max<'a>(a: &'a f64, b: &'a f64) -> (&'a f64, &'a f64) {
         (b, a)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{point::Point2d, segment::Segment};
+
+    #[test]
+    fn test_distance_to_point() {
+        let segment = Segment::new(Point2d::new(0.0, 0.0), Point2d::new(4.0, 0.0));
+
+        assert_eq!(segment.distance_to_point(&Point2d::new(2.0, 3.0)), 3.0);
+        assert_eq!(segment.distance_to_point(&Point2d::new(7.0, 4.0)), 5.0);
+        assert_eq!(segment.distance_to_point(&Point2d::new(1.0, 0.0)), 0.0);
+        assert_eq!(
+            segment.closest_point_to(&Point2d::new(-1.0, -1.0)),
+            Point2d::new(0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_distance_to_segment() {
+        let segment = Segment::new(Point2d::new(0.0, 0.0), Point2d::new(4.0, 0.0));
+
+        let parallel = Segment::new(Point2d::new(1.0, 2.0), Point2d::new(3.0, 2.0));
+        assert_eq!(segment.distance_to_segment(&parallel), 2.0);
+
+        let skewed = Segment::new(Point2d::new(5.0, 1.0), Point2d::new(8.0, 5.0));
+        let (a, b) = segment.closest_points_to_segment(&skewed);
+        assert_eq!((a, b), (Point2d::new(4.0, 0.0), Point2d::new(5.0, 1.0)));
+
+        let crossing = Segment::new(Point2d::new(1.0, -1.0), Point2d::new(3.0, 1.0));
+        let (a, b) = segment.closest_points_to_segment(&crossing);
+        assert_eq!(a, Point2d::new(2.0, 0.0));
+        assert_eq!(a, b);
+        assert_eq!(segment.distance_to_segment(&crossing), 0.0);
+    }
+}
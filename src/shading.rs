@@ -0,0 +1,294 @@
+use crate::affine::{Affine2, Transform};
+use crate::clip;
+use crate::point::Point2d;
+use crate::polygon::Polygon;
+
+/// A panel as seen in the plane the merged shadow was computed in: its outline there and the map
+/// from that plane back to the panel's own coordinates
+#[derive(Clone, Debug)]
+pub struct PanelFootprint {
+    pub outline: Polygon,
+    pub to_local: Affine2,
+}
+
+impl PanelFootprint {
+    /// A panel whose outline is already in its own coordinates
+    pub fn local(outline: Polygon) -> Self {
+        Self {
+            outline,
+            to_local: Affine2::identity(),
+        }
+    }
+}
+
+/// How much of a panel is in the shade. `shaded_polygons` are in the panel's own coordinates and
+/// may include holes, wound the other way around as usual. A concave shadow that the panel's
+/// outline cuts apart is a single polygon whose pieces are joined along the outline by bridges of
+/// zero width, see [`clip::intersect_convex`].
+#[derive(Clone, Debug)]
+pub struct PanelShading {
+    pub shaded_area: f64,
+    pub shaded_fraction: f64,
+    pub shaded_polygons: Vec<Polygon>,
+}
+
+impl PanelShading {
+    /// The part of `shadow` (e.g. the output of [`clip::sum`]) that falls onto `panel`, whose
+    /// outline must be convex
+    pub fn of(panel: &PanelFootprint, shadow: &[Polygon]) -> Self {
+        let outline = &panel.outline;
+        let shaded_parts = shadow
+            .iter()
+            .filter(|polygon| polygon.bounds.is_within(&outline.bounds, 0.0))
+            .filter_map(|polygon| clip::intersect_convex(polygon, outline))
+            .collect::<Vec<_>>();
+
+        let area_in_plane = shaded_parts.iter().map(Polygon::signed_area).sum::<f64>();
+        let shaded_polygons = shaded_parts
+            .iter()
+            .map(|polygon| polygon.transform(&panel.to_local))
+            .collect::<Vec<_>>();
+        Self {
+            shaded_area: shaded_polygons
+                .iter()
+                .map(Polygon::signed_area)
+                .sum::<f64>(),
+            shaded_fraction: (area_in_plane / outline.signed_area()).clamp(0.0, 1.0),
+            shaded_polygons,
+        }
+    }
+
+    /// A panel that only gets to see the back of the sun, given in its own coordinates
+    pub fn fully_shaded(outline: &Polygon) -> Self {
+        Self {
+            shaded_area: outline.signed_area(),
+            shaded_fraction: 1.0,
+            shaded_polygons: vec![outline.clone()],
+        }
+    }
+}
+
+/// The cells of a module: `rows` by `columns` cells covering the panel's outline, with rows going
+/// up the slope and columns along the row of panels. Each of the `substrings` (one per bypass
+/// diode) is a run of adjacent columns, so `columns` must be a multiple of `substrings`.
+#[derive(Clone, Debug)]
+pub struct CellGrid {
+    pub rows: usize,
+    pub columns: usize,
+    pub substrings: usize,
+}
+
+/// Shaded fraction of every cell, row by row from the bottom of the panel, and of every
+/// substring, where a substring is as shaded as its most shaded cell since that one limits the
+/// current of the whole substring
+#[derive(Clone, Debug)]
+pub struct CellShading {
+    pub cells: Vec<f64>,
+    pub substrings: Vec<f64>,
+}
+
+impl CellGrid {
+    /// Panics unless the columns can be split evenly among at least one substring
+    pub fn new(rows: usize, columns: usize, substrings: usize) -> Self {
+        let grid = Self {
+            rows,
+            columns,
+            substrings,
+        };
+        grid.check_wiring();
+        grid
+    }
+
+    fn check_wiring(&self) {
+        let is_wired_evenly = self.substrings > 0 && self.columns.is_multiple_of(self.substrings);
+        assert!(
+            is_wired_evenly,
+            "{} columns can't be split evenly into {} substrings",
+            self.columns, self.substrings
+        );
+    }
+
+    /// The cell in `row` and `column` within a panel whose own coordinates are bounded by
+    /// `outline`
+    pub fn cell(&self, outline: &Polygon, row: usize, column: usize) -> Polygon {
+        let bounds = &outline.bounds;
+        let width = (bounds.max_x - bounds.min_x) / self.columns as f64;
+        let height = (bounds.max_y - bounds.min_y) / self.rows as f64;
+        let min_x = bounds.min_x + column as f64 * width;
+        let min_y = bounds.min_y + row as f64 * height;
+        let p0 = Point2d::new(min_x, min_y);
+        let p1 = Point2d::new(min_x, min_y + height);
+        let p2 = Point2d::new(min_x + width, min_y + height);
+        let p3 = Point2d::new(min_x + width, min_y);
+        Polygon::from_points(vec![p0, p1, p2, p3])
+    }
+
+    /// Splits the shade of a panel, as found in [`PanelShading::shaded_polygons`], among its cells.
+    /// Panics if the grid is not wired evenly, see [`CellGrid::new`].
+    pub fn shading(&self, outline: &Polygon, shaded_polygons: &[Polygon]) -> CellShading {
+        self.check_wiring();
+
+        let mut cells = Vec::with_capacity(self.rows * self.columns);
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let cell = self.cell(outline, row, column);
+                let panel = PanelFootprint::local(cell);
+                cells.push(PanelShading::of(&panel, shaded_polygons).shaded_fraction);
+            }
+        }
+
+        let columns_per_substring = self.columns / self.substrings;
+        let substrings = (0..self.substrings)
+            .map(|substring| {
+                let first_column = substring * columns_per_substring;
+                let columns = first_column..first_column + columns_per_substring;
+                cells
+                    .chunks(self.columns)
+                    .flat_map(|row| row[columns.clone()].iter())
+                    .fold(0.0, |most: f64, fraction| most.max(*fraction))
+            })
+            .collect();
+
+        CellShading { cells, substrings }
+    }
+}
+
+/// Applies one merged shadow to every panel: the second half of projecting every shadow onto a
+/// common plane, merging them once with [`clip::sum`] and reading each panel's share off of it
+pub fn report(panels: &[PanelFootprint], shadow: &[Polygon]) -> Vec<PanelShading> {
+    panels
+        .iter()
+        .map(|panel| PanelShading::of(panel, shadow))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        affine::Affine2,
+        point::{Point2d, Point3d},
+        polygon::Polygon,
+        shading::{self, CellGrid, PanelFootprint},
+        tracker::SingleAxisLayout,
+    };
+
+    fn rectangle(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Polygon {
+        let p0 = Point2d::new(min_x, min_y);
+        let p1 = Point2d::new(min_x, max_y);
+        let p2 = Point2d::new(max_x, max_y);
+        let p3 = Point2d::new(max_x, min_y);
+        Polygon::from_points(vec![p0, p1, p2, p3])
+    }
+
+    #[test]
+    fn test_report() {
+        // the footprints are twice as large as the panels, which are 1 by 1
+        let to_local = |x: f64| Affine2::translation(-x, 0.0).then(&Affine2::scale(0.5, 0.5));
+        let left = PanelFootprint {
+            outline: rectangle(0.0, 0.0, 2.0, 2.0),
+            to_local: to_local(0.0),
+        };
+        let right = PanelFootprint {
+            outline: rectangle(3.0, 0.0, 5.0, 2.0),
+            to_local: to_local(3.0),
+        };
+        let far = PanelFootprint::local(rectangle(10.0, 0.0, 11.0, 1.0));
+
+        // covers the right half of the left panel and the left quarter of the right one, with a
+        // hole over the gap between them
+        let mut hole = rectangle(2.0, 0.5, 3.0, 1.5).points;
+        hole.reverse();
+        let shadow = vec![rectangle(1.0, 0.0, 3.5, 2.0), Polygon::from_points(hole)];
+
+        let report = shading::report(&[left, right, far], &shadow);
+
+        assert_eq!(report[0].shaded_fraction, 0.5);
+        assert_eq!(report[0].shaded_area, 0.5);
+        assert_eq!(report[0].shaded_polygons[0].bounds.min_x, 0.5);
+        assert_eq!(report[1].shaded_fraction, 0.25);
+        assert_eq!(report[1].shaded_area, 0.25);
+        assert_eq!(report[2].shaded_fraction, 0.0);
+        assert!(report[2].shaded_polygons.is_empty());
+    }
+
+    #[test]
+    fn test_cell_shading() {
+        let outline = rectangle(-1.5, -1.0, 1.5, 1.0);
+        let grid = CellGrid::new(2, 6, 3);
+
+        // the bottom row, and half of the top row in the first column
+        let shadow = vec![
+            rectangle(-2.0, -2.0, 2.0, 0.0),
+            rectangle(-1.5, 0.0, -1.25, 1.0),
+        ];
+        let cells = grid.shading(&outline, &shadow);
+
+        assert_eq!(cells.cells.len(), 12);
+        assert!(cells.cells[..6].iter().all(|fraction| *fraction == 1.0));
+        assert_eq!(cells.cells[6], 0.5);
+        assert!(cells.cells[7..].iter().all(|fraction| *fraction == 0.0));
+        assert_eq!(cells.substrings, vec![1.0, 1.0, 1.0]);
+
+        let shadow = vec![rectangle(0.5, 0.5, 1.5, 1.0)];
+        let cells = grid.shading(&outline, &shadow);
+        assert_eq!(cells.substrings, vec![0.0, 0.0, 0.5]);
+    }
+
+    #[test]
+    #[should_panic(expected = "substrings")]
+    fn test_cell_grid_needs_a_substring() {
+        CellGrid::new(2, 6, 0);
+    }
+
+    #[test]
+    fn test_cells_keep_their_index_through_the_day() {
+        let layout = SingleAxisLayout {
+            rows: 1,
+            panels_per_row: 1,
+            panel_width: 2.0,
+            panel_height: 1.0,
+            axis_azimuth: 0.0,
+            axis_height: 1.5,
+            pitch: 5.0,
+            gap: 0.0,
+            max_angle: 60.0,
+            backtracking: false,
+        };
+        let grid = CellGrid::new(2, 6, 3);
+        let morning = layout.scene(30.0).panels[0].clone();
+        let afternoon = layout.scene(-30.0).panels[0].clone();
+        let outline = morning.outline();
+
+        // a spot of dirt in the first row and fifth column, which the tracker turns from facing
+        // east to facing west by rotating the panel about its axis, pointing north
+        let dirt = Point2d::new(0.5, -0.25);
+        let seen_in_the_morning = morning.plane().to_world(&dirt);
+        let (sin, cos) = (-60f64).to_radians().sin_cos();
+        let x = seen_in_the_morning.x - afternoon.center.x;
+        let z = seen_in_the_morning.z - afternoon.center.z;
+        let seen_in_the_afternoon = Point3d::new(
+            afternoon.center.x + x * cos + z * sin,
+            seen_in_the_morning.y,
+            afternoon.center.z - x * sin + z * cos,
+        );
+
+        let shaded_cell = |point: Point2d| {
+            let spot = rectangle(
+                point.x - 0.01,
+                point.y - 0.01,
+                point.x + 0.01,
+                point.y + 0.01,
+            );
+            let cells = grid.shading(&outline, &[spot]).cells;
+            let shaded = (0..cells.len())
+                .filter(|&i| cells[i] > 0.0)
+                .collect::<Vec<_>>();
+            assert_eq!(shaded.len(), 1);
+            shaded[0]
+        };
+        let in_the_morning = shaded_cell(dirt);
+        let in_the_afternoon = shaded_cell(afternoon.plane().to_local(&seen_in_the_afternoon));
+        assert_eq!(in_the_morning, 4);
+        assert_eq!(in_the_afternoon, in_the_morning);
+    }
+}
@@ -0,0 +1,277 @@
+use crate::point::Point2d;
+use crate::polygon::Polygon;
+use crate::segment::Segment;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Hausdorff distance between the vertices of two sets of polygons: how far the furthest vertex
+/// of either set is from the nearest vertex of the other set.
+///
+/// Cheap but sensitive to how boundaries are sampled, see [`hausdorff`] for the distance between
+/// the boundaries themselves.
+pub fn discrete_hausdorff(a: &[Polygon], b: &[Polygon]) -> f64 {
+    let directed = |from: &[Polygon], to: &[Polygon]| {
+        vertices(from)
+            .map(|point| {
+                vertices(to)
+                    .map(|other| point.distance_to(other))
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .fold(0.0, f64::max)
+    };
+    directed(a, b).max(directed(b, a))
+}
+
+/// Hausdorff distance between the boundaries of two sets of polygons, e.g. the output of
+/// [`crate::clip::sum`] and a reference shape: how far the furthest point on either boundary is
+/// from the other boundary.
+///
+/// The result is never larger than the exact distance and never smaller by more than `tolerance`.
+///
+/// Panics if `tolerance` is not positive, since the search would never stop splitting edges.
+pub fn hausdorff(a: &[Polygon], b: &[Polygon], tolerance: f64) -> f64 {
+    assert!(
+        tolerance > 0.0,
+        "the tolerance must be positive, got {tolerance}"
+    );
+    directed_hausdorff(a, b, tolerance).max(directed_hausdorff(b, a, tolerance))
+}
+
+/// Discrete Fréchet distance between two rings: the shortest leash that lets two walkers go
+/// around each ring, vertex by vertex and never backwards, while staying connected.
+///
+/// Rings have no natural start, so every vertex of `b` is tried as the start of the walk, and `b`
+/// is walked backwards if it is wound the other way around.
+pub fn discrete_frechet(a: &Polygon, b: &Polygon) -> f64 {
+    let mut b_points = b.points.clone();
+    if a.signed_area() * b.signed_area() < 0.0 {
+        b_points.reverse();
+    }
+
+    let closed = |points: &[Point2d]| {
+        let mut closed = points.to_vec();
+        closed.push(points[0].clone());
+        closed
+    };
+    let a_points = closed(&a.points);
+
+    (0..b_points.len())
+        .map(|start| {
+            let mut rotated = b_points.clone();
+            rotated.rotate_left(start);
+            coupling(&a_points, &closed(&rotated))
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn vertices(polygons: &[Polygon]) -> impl Iterator<Item = &Point2d> {
+    polygons.iter().flat_map(|polygon| polygon.iter_points())
+}
+
+// Eiter and Mannila's dynamic programming over pairs of vertices
+fn coupling(a: &[Point2d], b: &[Point2d]) -> f64 {
+    let m = b.len();
+    let mut previous_row = vec![f64::INFINITY; m];
+    let mut row = vec![f64::INFINITY; m];
+    for (i, point) in a.iter().enumerate() {
+        for j in 0..m {
+            let distance = point.distance_to(&b[j]);
+            let reachable = match (i, j) {
+                (0, 0) => 0.0,
+                (0, _) => row[j - 1],
+                (_, 0) => previous_row[0],
+                _ => previous_row[j].min(previous_row[j - 1]).min(row[j - 1]),
+            };
+            row[j] = reachable.max(distance);
+        }
+        std::mem::swap(&mut previous_row, &mut row);
+    }
+    previous_row[m - 1]
+}
+
+// the distance from a point on a's boundary to b's boundary changes no faster than the point
+// moves along a's boundary, and the distance to any single segment of b is convex along a straight
+// stretch of a: both bound the distance over a whole stretch from the distances at its ends. The
+// stretch with the highest bound is split until no stretch can beat the best distance found so
+// far by more than tolerance
+fn directed_hausdorff(a: &[Polygon], b: &[Polygon], tolerance: f64) -> f64 {
+    let targets = b
+        .iter()
+        .flat_map(|polygon| polygon.segments.iter())
+        .collect::<Vec<_>>();
+    let distance_to_b = |point: &Point2d| {
+        targets
+            .iter()
+            .map(|segment| segment.distance_to_point(point))
+            .fold(f64::INFINITY, f64::min)
+    };
+    let stretch = |segment: Segment, start: f64, end: f64| {
+        let lipschitz_bound = (start + end + segment.length()) / 2.0;
+        let convex_bound = targets
+            .iter()
+            .map(|target| {
+                let to_start = target.distance_to_point(&segment.start);
+                let to_end = target.distance_to_point(&segment.end);
+                to_start.max(to_end)
+            })
+            .fold(f64::INFINITY, f64::min);
+        Stretch {
+            segment,
+            start,
+            end,
+            upper_bound: lipschitz_bound.min(convex_bound),
+        }
+    };
+
+    let mut best: f64 = 0.0;
+    let mut stretches = BinaryHeap::new();
+    for segment in a.iter().flat_map(|polygon| polygon.segments.iter()) {
+        let start = distance_to_b(&segment.start);
+        let end = distance_to_b(&segment.end);
+        best = best.max(start).max(end);
+        stretches.push(stretch(segment.clone(), start, end));
+    }
+
+    while let Some(Stretch {
+        segment,
+        start,
+        end,
+        upper_bound,
+    }) = stretches.pop()
+    {
+        if upper_bound <= best + tolerance {
+            break;
+        }
+
+        let middle = Point2d::new(
+            (segment.start.x + segment.end.x) / 2.0,
+            (segment.start.y + segment.end.y) / 2.0,
+        );
+        let at_middle = distance_to_b(&middle);
+        best = best.max(at_middle);
+        let first_half = Segment::new(segment.start.clone(), middle.clone());
+        let second_half = Segment::new(middle, segment.end.clone());
+        stretches.push(stretch(first_half, start, at_middle));
+        stretches.push(stretch(second_half, at_middle, end));
+    }
+
+    best
+}
+
+struct Stretch {
+    segment: Segment,
+    start: f64,
+    end: f64,
+    upper_bound: f64,
+}
+
+impl Ord for Stretch {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.upper_bound.partial_cmp(&other.upper_bound).unwrap()
+    }
+}
+
+impl PartialOrd for Stretch {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Stretch {
+    fn eq(&self, other: &Self) -> bool {
+        self.upper_bound == other.upper_bound
+    }
+}
+
+impl Eq for Stretch {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{point::Point2d, polygon::Polygon, similarity};
+
+    fn square() -> Polygon {
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 2.0);
+        let p2 = Point2d::new(2.0, 2.0);
+        let p3 = Point2d::new(2.0, 0.0);
+        let points = vec![p0, p1, p2, p3];
+        Polygon::from_points(points)
+    }
+
+    #[test]
+    fn test_hausdorff_ignores_how_the_boundary_is_sampled() {
+        let square = square();
+
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 2.0);
+        let p2 = Point2d::new(2.0, 2.0);
+        let p3 = Point2d::new(2.0, 0.0);
+        let p4 = Point2d::new(1.0, 0.0);
+        let points = vec![p0, p1, p2, p3, p4];
+        let split_square = Polygon::from_points(points);
+
+        let a = [square];
+        let b = [split_square];
+        assert_eq!(similarity::discrete_hausdorff(&a, &b), 1.0);
+        assert_eq!(similarity::hausdorff(&a, &b, 1e-9), 0.0);
+    }
+
+    #[test]
+    fn test_hausdorff_finds_the_furthest_point_inside_of_an_edge() {
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 1.0);
+        let p2 = Point2d::new(10.0, 1.0);
+        let p3 = Point2d::new(10.0, 0.0);
+        let points = vec![p0, p1, p2, p3];
+        let rectangle = Polygon::from_points(points);
+
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 1.0);
+        let p2 = Point2d::new(1.0, 1.0);
+        let p3 = Point2d::new(1.0, 0.0);
+        let points = vec![p0, p1, p2, p3];
+        let left_square = Polygon::from_points(points);
+
+        let p0 = Point2d::new(9.0, 0.0);
+        let p1 = Point2d::new(9.0, 1.0);
+        let p2 = Point2d::new(10.0, 1.0);
+        let p3 = Point2d::new(10.0, 0.0);
+        let points = vec![p0, p1, p2, p3];
+        let right_square = Polygon::from_points(points);
+
+        // every vertex of the rectangle is a vertex of a square, but the middle of its long edges
+        // is 4 away from both squares
+        let a = [rectangle];
+        let b = [left_square, right_square];
+        assert_eq!(similarity::discrete_hausdorff(&a, &b), 1.0);
+
+        let tolerance = 1e-6;
+        let distance = similarity::hausdorff(&a, &b, tolerance);
+        assert!(distance <= 4.0 && distance >= 4.0 - tolerance);
+    }
+
+    #[test]
+    #[should_panic(expected = "the tolerance must be positive")]
+    fn test_hausdorff_rejects_a_tolerance_of_zero() {
+        let a = [square()];
+        similarity::hausdorff(&a, &a, 0.0);
+    }
+
+    #[test]
+    fn test_discrete_frechet() {
+        let square = square();
+
+        let mut points = square.points.clone();
+        points.rotate_left(2);
+        points.reverse();
+        let reversed = Polygon::from_points(points);
+        assert_eq!(similarity::discrete_frechet(&square, &reversed), 0.0);
+
+        let shifted = square
+            .iter_points()
+            .map(|point| Point2d::new(point.x + 0.5, point.y))
+            .collect();
+        let shifted = Polygon::from_points(shifted);
+        assert_eq!(similarity::discrete_frechet(&square, &shifted), 0.5);
+    }
+}
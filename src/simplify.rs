@@ -0,0 +1,618 @@
+use crate::bounds::Bounds;
+use crate::point::Point2d;
+use crate::polygon::{ring_contains, turn, Polygon};
+use crate::segment::Segment;
+use crate::vector::Vector2d;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// Drops vertices that sit exactly on the line through their neighbours, such as the ones left
+/// behind where [`crate::clip::sum`] split a segment, as well as repeated points. Rings left with
+/// fewer than 3 points are dropped.
+pub fn remove_collinear(polygons: &[Polygon]) -> Vec<Polygon> {
+    polygons
+        .iter()
+        .filter_map(|polygon| {
+            let mut points = polygon.points.clone();
+            points.dedup();
+            if points.len() > 1 && points.first() == points.last() {
+                points.pop();
+            }
+
+            // a vertex is unlinked as soon as it is collinear, then its neighbours are checked
+            // again since either may have just become collinear
+            let n = points.len();
+            let mut prev = (0..n).map(|i| (i + n - 1) % n).collect::<Vec<_>>();
+            let mut next = (0..n).map(|i| (i + 1) % n).collect::<Vec<_>>();
+            let mut kept = vec![true; n];
+            let mut count = n;
+            let mut to_check = (0..n).rev().collect::<Vec<_>>();
+            while let Some(i) = to_check.pop() {
+                if count < 3 {
+                    break;
+                }
+                if !kept[i] || turn(&points[prev[i]], &points[i], &points[next[i]]) != 0.0 {
+                    continue;
+                }
+                kept[i] = false;
+                count -= 1;
+                next[prev[i]] = next[i];
+                prev[next[i]] = prev[i];
+                to_check.extend([next[i], prev[i]]);
+            }
+
+            let mut kept = kept.into_iter();
+            points.retain(|_| kept.next().unwrap());
+            (points.len() >= 3).then(|| Polygon::from_points(points))
+        })
+        .collect()
+}
+
+/// Douglas-Peucker simplification of every ring: a run of vertices is replaced by a single
+/// segment as long as none of them lies further than `tolerance` from it.
+///
+/// Topology is preserved: a shortcut is refused if it would cross or touch any ring (including
+/// its own) or leave a vertex of another ring on the wrong side, and no ring drops below 3 points.
+pub fn douglas_peucker(polygons: &[Polygon], tolerance: f64) -> Vec<Polygon> {
+    let mut rings = Rings::new(polygons);
+
+    for ring in 0..rings.points.len() {
+        let points = &rings.points[ring];
+        let n = points.len();
+        if n <= 3 {
+            continue;
+        }
+
+        let farthest = (1..n)
+            .max_by(|&i, &j| {
+                let di = Vector2d::from_points(&points[0], &points[i]).norm_sq();
+                let dj = Vector2d::from_points(&points[0], &points[j]).norm_sq();
+                di.partial_cmp(&dj).unwrap()
+            })
+            .unwrap();
+
+        let mut chains = vec![(0, farthest), (farthest, n)];
+        while let Some((start, end)) = chains.pop() {
+            if end - start < 2 {
+                continue;
+            }
+
+            let points = &rings.points[ring];
+            let shortcut = Segment::new(points[start].clone(), points[end % n].clone());
+            let (farthest, distance) = (start + 1..end)
+                .map(|i| (i, shortcut.distance_to_point(&points[i])))
+                .max_by(|(_, di), (_, dj)| di.partial_cmp(dj).unwrap())
+                .unwrap();
+
+            let chain = (start..=end).map(|i| i % n).collect::<Vec<_>>();
+            if distance <= tolerance && rings.can_shortcut(ring, &chain) {
+                rings.shortcut(ring, &chain);
+            } else {
+                chains.push((start, farthest));
+                chains.push((farthest, end));
+            }
+        }
+    }
+
+    rings.into_polygons()
+}
+
+/// Visvalingam-Whyatt simplification of every ring: the vertex spanning the smallest triangle with
+/// its neighbours is removed, over and over, while that triangle is smaller than `area_tolerance`.
+///
+/// Topology is preserved the same way as in [`douglas_peucker`].
+pub fn visvalingam(polygons: &[Polygon], area_tolerance: f64) -> Vec<Polygon> {
+    let mut rings = Rings::new(polygons);
+
+    let mut candidates = BinaryHeap::new();
+    for ring in 0..rings.points.len() {
+        for vertex in 0..rings.points[ring].len() {
+            candidates.push(rings.candidate(ring, vertex));
+        }
+    }
+
+    while let Some(candidate) = candidates.pop() {
+        if candidate.area > area_tolerance {
+            break;
+        }
+
+        let Candidate {
+            ring,
+            vertex,
+            prev,
+            next,
+            ..
+        } = candidate;
+        let is_stale = !rings.kept[ring][vertex]
+            || rings.prev[ring][vertex] != prev
+            || rings.next[ring][vertex] != next;
+        if is_stale {
+            continue;
+        }
+
+        let chain = [prev, vertex, next];
+        if !rings.can_shortcut(ring, &chain) {
+            continue;
+        }
+        rings.shortcut(ring, &chain);
+        candidates.push(rings.candidate(ring, prev));
+        candidates.push(rings.candidate(ring, next));
+    }
+
+    rings.into_polygons()
+}
+
+// every ring of a multipolygon as a doubly linked list whose vertices can be removed
+struct Rings {
+    points: Vec<Vec<Point2d>>,
+    kept: Vec<Vec<bool>>,
+    // how many vertices of every ring are kept, and one of them
+    counts: Vec<usize>,
+    kept_vertex: Vec<usize>,
+    // of every ring before simplification, which the kept vertices never leave
+    bounds: Vec<Bounds>,
+    prev: Vec<Vec<usize>>,
+    next: Vec<Vec<usize>>,
+    edges: EdgeIndex,
+}
+
+impl Rings {
+    fn new(polygons: &[Polygon]) -> Self {
+        let points = polygons
+            .iter()
+            .map(|polygon| polygon.points.clone())
+            .collect::<Vec<_>>();
+        let kept = points.iter().map(|ring| vec![true; ring.len()]).collect();
+        let prev = points
+            .iter()
+            .map(|ring| {
+                let n = ring.len();
+                (0..n).map(|i| (i + n - 1) % n).collect()
+            })
+            .collect();
+        let next = points
+            .iter()
+            .map(|ring| {
+                let n = ring.len();
+                (0..n).map(|i| (i + 1) % n).collect()
+            })
+            .collect();
+
+        let counts = points.iter().map(Vec::len).collect();
+        let kept_vertex = vec![0; points.len()];
+        let bounds = polygons
+            .iter()
+            .map(|polygon| polygon.bounds.clone())
+            .collect();
+        let edges = EdgeIndex::new(&points);
+
+        Self {
+            points,
+            kept,
+            counts,
+            kept_vertex,
+            bounds,
+            prev,
+            next,
+            edges,
+        }
+    }
+
+    // whether the vertices strictly between the first and last vertex of chain, which are
+    // consecutive in ring, can be replaced by a single segment
+    fn can_shortcut(&mut self, ring: usize, chain: &[usize]) -> bool {
+        let removed = chain.len() - 2;
+        if self.counts[ring] - removed < 3 {
+            return false;
+        }
+
+        let region = chain
+            .iter()
+            .map(|&i| self.points[ring][i].clone())
+            .collect::<Vec<_>>();
+        let a = &region[0];
+        let c = &region[region.len() - 1];
+        let replaced = chain[..chain.len() - 1].iter().collect::<HashSet<_>>();
+
+        let (kept, next) = (&self.kept, &self.next);
+        let is_kept =
+            |edge: &Edge| kept[edge.ring][edge.start] && next[edge.ring][edge.start] == edge.end;
+        for Edge {
+            ring: other,
+            start,
+            end,
+        } in self.edges.along(a, c, is_kept)
+        {
+            if other == ring && replaced.contains(&start) {
+                continue;
+            }
+            if crosses(a, c, &self.points[other][start], &self.points[other][end]) {
+                return false;
+            }
+        }
+
+        // nothing may be left stranded between the removed vertices and the shortcut. Rings
+        // never cross each other, nor the shortcut now, so every other ring is either wholly in
+        // there or wholly out, and so is the rest of this ring, from the vertex after the chain
+        let bounds = Bounds::from_points(&region);
+        let after = self.next[ring][chain[chain.len() - 1]];
+        if ring_contains(&region, &self.points[ring][after]) {
+            return false;
+        }
+        for other in 0..self.points.len() {
+            if other == ring || !self.bounds[other].is_within(&bounds, 0.0) {
+                continue;
+            }
+            let point = &self.points[other][self.kept_vertex[other]];
+            if ring_contains(&region, point) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn shortcut(&mut self, ring: usize, chain: &[usize]) {
+        let first = chain[0];
+        let last = chain[chain.len() - 1];
+        for &vertex in &chain[1..chain.len() - 1] {
+            self.kept[ring][vertex] = false;
+        }
+        self.counts[ring] -= chain.len() - 2;
+        self.kept_vertex[ring] = first;
+        self.next[ring][first] = last;
+        self.prev[ring][last] = first;
+        let points = &self.points[ring];
+        let edge = Edge {
+            ring,
+            start: first,
+            end: last,
+        };
+        self.edges.insert(edge, &points[first], &points[last]);
+    }
+
+    fn candidate(&self, ring: usize, vertex: usize) -> Candidate {
+        let prev = self.prev[ring][vertex];
+        let next = self.next[ring][vertex];
+        let points = &self.points[ring];
+        let area = turn(&points[prev], &points[vertex], &points[next]).abs() / 2.0;
+        Candidate {
+            area,
+            ring,
+            vertex,
+            prev,
+            next,
+        }
+    }
+
+    fn into_polygons(self) -> Vec<Polygon> {
+        self.points
+            .into_iter()
+            .zip(self.kept)
+            .map(|(points, kept)| {
+                let points = points
+                    .into_iter()
+                    .zip(kept)
+                    .filter_map(|(point, kept)| kept.then_some(point))
+                    .collect();
+                Polygon::from_points(points)
+            })
+            .collect()
+    }
+}
+
+// the edges of the rings by the cells of a grid they run through, so that only the edges near a
+// shortcut are looked at. The edges a shortcut replaces are left behind until a lookup comes
+// across them and tells from the links of the rings that they are gone.
+struct EdgeIndex {
+    min_x: f64,
+    min_y: f64,
+    cell_size: f64,
+    cells: HashMap<(i64, i64), Vec<Edge>>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Edge {
+    ring: usize,
+    start: usize,
+    end: usize,
+}
+
+impl EdgeIndex {
+    fn new(rings: &[Vec<Point2d>]) -> Self {
+        let all_points = rings.iter().flatten().cloned().collect::<Vec<_>>();
+        let mut index = Self {
+            min_x: 0.0,
+            min_y: 0.0,
+            cell_size: 1.0,
+            cells: HashMap::new(),
+        };
+        if all_points.is_empty() {
+            return index;
+        }
+
+        // cells about as large as edges, so that there are few edges per cell along the rings
+        let bounds = Bounds::from_points(&all_points);
+        let perimeter = rings
+            .iter()
+            .map(|points| {
+                let n = points.len();
+                (0..n)
+                    .map(|i| points[i].distance_to(&points[(i + 1) % n]))
+                    .sum::<f64>()
+            })
+            .sum::<f64>();
+        index.min_x = bounds.min_x;
+        index.min_y = bounds.min_y;
+        let mean_edge = perimeter / all_points.len() as f64;
+        if mean_edge > 0.0 {
+            index.cell_size = mean_edge;
+        }
+
+        for (ring, points) in rings.iter().enumerate() {
+            let n = points.len();
+            for vertex in 0..n {
+                let end = (vertex + 1) % n;
+                let edge = Edge {
+                    ring,
+                    start: vertex,
+                    end,
+                };
+                index.insert(edge, &points[vertex], &points[end]);
+            }
+        }
+        index
+    }
+
+    fn cell(&self, x: f64, y: f64) -> (i64, i64) {
+        let column = ((x - self.min_x) / self.cell_size).floor() as i64;
+        let row = ((y - self.min_y) / self.cell_size).floor() as i64;
+        (column, row)
+    }
+
+    fn cells_of(&self, bounds: &Bounds) -> impl Iterator<Item = (i64, i64)> {
+        let (min_column, min_row) = self.cell(bounds.min_x, bounds.min_y);
+        let (max_column, max_row) = self.cell(bounds.max_x, bounds.max_y);
+        (min_column..=max_column)
+            .flat_map(move |column| (min_row..=max_row).map(move |row| (column, row)))
+    }
+
+    fn insert(&mut self, edge: Edge, start: &Point2d, end: &Point2d) {
+        for cell in self.cells_along(start, end) {
+            self.cells.entry(cell).or_default().push(edge);
+        }
+    }
+
+    // the cells along the segment rather than every cell of its bounds, which a long diagonal
+    // segment would have plenty of: a piece per cell it runs along, each in the cells of its
+    // bounds
+    fn cells_along(&self, start: &Point2d, end: &Point2d) -> Vec<(i64, i64)> {
+        let pieces = (start.distance_to(end) / self.cell_size).ceil().max(1.0) as usize;
+        let along = |t: f64| {
+            Point2d::new(
+                start.x + t * (end.x - start.x),
+                start.y + t * (end.y - start.y),
+            )
+        };
+        let mut cells = Vec::with_capacity(2 * pieces);
+        for piece in 0..pieces {
+            let from = along(piece as f64 / pieces as f64);
+            let to = along((piece + 1) as f64 / pieces as f64);
+            cells.extend(self.cells_of(&Bounds::from_points(&[from, to])));
+        }
+        cells.sort_unstable();
+        cells.dedup();
+        cells
+    }
+
+    // every edge in a cell the segment runs through, once each, forgetting along the way about
+    // the ones that are gone
+    fn along(
+        &mut self,
+        start: &Point2d,
+        end: &Point2d,
+        is_kept: impl Fn(&Edge) -> bool,
+    ) -> Vec<Edge> {
+        let mut edges = Vec::new();
+        for cell in self.cells_along(start, end) {
+            if let Some(in_cell) = self.cells.get_mut(&cell) {
+                in_cell.retain(&is_kept);
+                edges.extend(in_cell.iter().copied());
+            }
+        }
+        edges.sort_unstable();
+        edges.dedup();
+        edges
+    }
+}
+
+struct Candidate {
+    area: f64,
+    ring: usize,
+    vertex: usize,
+    prev: usize,
+    next: usize,
+}
+
+// BinaryHeap is a max heap, so the smallest area has to compare as the greatest
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.area.partial_cmp(&self.area).unwrap()
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.area == other.area
+    }
+}
+
+impl Eq for Candidate {}
+
+// whether segments a-c and p-q cross or touch anywhere but at an endpoint they share
+fn crosses(a: &Point2d, c: &Point2d, p: &Point2d, q: &Point2d) -> bool {
+    let shares_endpoint = a == p || a == q || c == p || c == q;
+    if shares_endpoint {
+        // only a problem if they overlap along a line
+        let (shared, other_ac, other_pq) = if a == p {
+            (a, c, q)
+        } else if a == q {
+            (a, c, p)
+        } else if c == p {
+            (c, a, q)
+        } else {
+            (c, a, p)
+        };
+        let are_collinear = turn(shared, other_ac, other_pq) == 0.0;
+        let same_direction = Vector2d::from_points(shared, other_ac)
+            .dot(&Vector2d::from_points(shared, other_pq))
+            > 0.0;
+        return are_collinear && same_direction;
+    }
+
+    let d1 = turn(a, c, p);
+    let d2 = turn(a, c, q);
+    let d3 = turn(p, q, a);
+    let d4 = turn(p, q, c);
+    if d1 * d2 < 0.0 && d3 * d4 < 0.0 {
+        return true;
+    }
+
+    (d1 == 0.0 && is_within(p, a, c))
+        || (d2 == 0.0 && is_within(q, a, c))
+        || (d3 == 0.0 && is_within(a, p, q))
+        || (d4 == 0.0 && is_within(c, p, q))
+}
+
+// point is known to be on the line through start and end
+fn is_within(point: &Point2d, start: &Point2d, end: &Point2d) -> bool {
+    point.x >= start.x.min(end.x)
+        && point.x <= start.x.max(end.x)
+        && point.y >= start.y.min(end.y)
+        && point.y <= start.y.max(end.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        point::Point2d,
+        polygon::{ring_contains, Polygon},
+        simplify,
+    };
+
+    #[test]
+    fn test_remove_collinear_points_left_behind_by_split_segments() {
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 1.0);
+        let p2 = Point2d::new(0.0, 2.0);
+        let p3 = Point2d::new(2.0, 2.0);
+        let p4 = Point2d::new(2.0, 2.0);
+        let p5 = Point2d::new(2.0, 0.0);
+        let p6 = Point2d::new(1.0, 0.0);
+        let points = vec![p0, p1, p2, p3, p4, p5, p6];
+        let square = Polygon::from_points(points);
+
+        let actual_polygons = simplify::remove_collinear(&[square]);
+
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 2.0);
+        let p2 = Point2d::new(2.0, 2.0);
+        let p3 = Point2d::new(2.0, 0.0);
+        let points = vec![p0, p1, p2, p3];
+        let expected_polygons = vec![Polygon::from_points(points)];
+
+        assert_eq!(actual_polygons, expected_polygons);
+    }
+
+    fn noisy_square() -> Polygon {
+        let mut points = vec![];
+        for i in 0..10 {
+            let wiggle = if i % 2 == 0 { 0.01 } else { -0.01 };
+            points.push(Point2d::new(wiggle, i as f64));
+        }
+        for i in 0..10 {
+            let wiggle = if i % 2 == 0 { 0.01 } else { -0.01 };
+            points.push(Point2d::new(i as f64, 10.0 + wiggle));
+        }
+        for i in 0..10 {
+            points.push(Point2d::new(10.0, 10.0 - i as f64));
+        }
+        for i in 0..10 {
+            points.push(Point2d::new(10.0 - i as f64, 0.0));
+        }
+        Polygon::from_points(points)
+    }
+
+    #[test]
+    fn test_douglas_peucker_and_visvalingam_reduce_a_noisy_square_to_its_corners() {
+        let square = noisy_square();
+
+        let simplified = simplify::douglas_peucker(std::slice::from_ref(&square), 0.1);
+        assert_eq!(simplified[0].points.len(), 4);
+
+        let simplified = simplify::visvalingam(&[square], 0.5);
+        assert_eq!(simplified[0].points.len(), 4);
+    }
+
+    #[test]
+    fn test_simplification_does_not_swallow_a_nearby_hole() {
+        let square = noisy_square();
+
+        // a tiny hole within the wiggles of the left edge
+        let p0 = Point2d::new(-0.008, 4.95);
+        let p1 = Point2d::new(-0.002, 4.95);
+        let p2 = Point2d::new(-0.002, 5.05);
+        let p3 = Point2d::new(-0.008, 5.05);
+        let points = vec![p0, p1, p2, p3];
+        let hole = Polygon::from_points(points);
+        assert!(hole
+            .iter_points()
+            .all(|point| ring_contains(&square.points, point)));
+
+        for simplified in [
+            simplify::douglas_peucker(&[square.clone(), hole.clone()], 0.1),
+            simplify::visvalingam(&[square.clone(), hole.clone()], 0.5),
+        ] {
+            assert!(simplified[0].points.len() > 4);
+            assert!(simplified[1]
+                .iter_points()
+                .all(|point| ring_contains(&simplified[0].points, point)));
+        }
+    }
+
+    #[test]
+    fn test_simplifying_a_ring_with_many_vertices() {
+        // a circle, wiggling by less than the tolerance, around a smaller one
+        let circle = |radius: f64, n: usize, wiggle: f64| {
+            let points = (0..n)
+                .map(|i| {
+                    let angle = -std::f64::consts::TAU * i as f64 / n as f64;
+                    let radius = radius + if i % 2 == 0 { wiggle } else { -wiggle };
+                    Point2d::new(radius * angle.cos(), radius * angle.sin())
+                })
+                .collect();
+            Polygon::from_points(points)
+        };
+        let outer = circle(100.0, 8_000, 0.001);
+        let mut inner = circle(50.0, 4_000, 0.001).points;
+        inner.reverse();
+        let inner = Polygon::from_points(inner);
+
+        for simplified in [
+            simplify::douglas_peucker(&[outer.clone(), inner.clone()], 0.01),
+            simplify::visvalingam(&[outer.clone(), inner.clone()], 0.01),
+        ] {
+            assert!(simplified[0].points.len() < 800);
+            assert!(simplified[1].points.len() < 400);
+            assert!(simplified[1]
+                .iter_points()
+                .all(|point| ring_contains(&simplified[0].points, point)));
+        }
+    }
+}
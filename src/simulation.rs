@@ -0,0 +1,195 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+use crate::scene::Scene;
+use crate::solar::{Location, SunPosition};
+use crate::tracker::SingleAxisLayout;
+
+/// Panels that may move with the sun
+pub trait Array {
+    /// Where the panels are for a given sun position, `None` if they shouldn't be simulated then
+    fn scene_at(&self, sun: &SunPosition) -> Option<Cow<'_, Scene>>;
+}
+
+impl Array for Scene {
+    fn scene_at(&self, _sun: &SunPosition) -> Option<Cow<'_, Scene>> {
+        Some(Cow::Borrowed(self))
+    }
+}
+
+impl Array for SingleAxisLayout {
+    fn scene_at(&self, sun: &SunPosition) -> Option<Cow<'_, Scene>> {
+        SingleAxisLayout::scene_at(self, sun).map(Cow::Owned)
+    }
+}
+
+/// One row per daylight timestamp with the sun position, the shaded fraction of every panel and
+/// the cosine of the angle at which the sun hits every panel, 0 when it shines on its back
+#[derive(Clone, Debug, Default)]
+pub struct ShadingTable {
+    pub timestamps: Vec<i64>,
+    pub suns: Vec<SunPosition>,
+    pub fractions: Vec<Vec<f64>>,
+    pub incidences: Vec<Vec<f64>>,
+}
+
+impl ShadingTable {
+    /// Share of the direct sunlight reaching every panel's plane over the daylight timestamps
+    /// that the shade takes away: the shaded fractions weighted by the cosine of the angle of
+    /// incidence, so hours when the sun grazes the panel or shines on its back count for little
+    /// or nothing. 0 for a panel the sun never shines on.
+    pub fn mean_fractions(&self) -> Vec<f64> {
+        let Some(first) = self.fractions.first() else {
+            return Vec::new();
+        };
+        let mut shaded = vec![0.0; first.len()];
+        let mut total = vec![0.0; first.len()];
+        for (fractions, incidences) in self.fractions.iter().zip(self.incidences.iter()) {
+            for (panel, (fraction, incidence)) in fractions.iter().zip(incidences).enumerate() {
+                shaded[panel] += fraction * incidence;
+                total[panel] += incidence;
+            }
+        }
+        shaded
+            .into_iter()
+            .zip(total)
+            .map(|(shaded, total)| if total > 0.0 { shaded / total } else { 0.0 })
+            .collect()
+    }
+
+    /// Comma separated values with a header: timestamp, azimuth, elevation and one column per panel
+    pub fn write_csv(&self, writer: &mut impl Write) -> io::Result<()> {
+        let panel_count = self.fractions.first().map_or(0, Vec::len);
+        write!(writer, "timestamp,azimuth,elevation")?;
+        for panel in 0..panel_count {
+            write!(writer, ",panel_{panel}")?;
+        }
+        writeln!(writer)?;
+
+        for ((timestamp, sun), fractions) in self
+            .timestamps
+            .iter()
+            .zip(self.suns.iter())
+            .zip(self.fractions.iter())
+        {
+            write!(writer, "{timestamp},{},{}", sun.azimuth, sun.elevation)?;
+            for fraction in fractions {
+                write!(writer, ",{fraction}")?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Shaded fraction of every panel at every timestamp (seconds since the unix epoch, UTC) when the
+/// sun is up, e.g. `(start..end).step_by(3600)` for hourly values. Panels the sun shines on from
+/// behind count as fully shaded, with an incidence of 0.
+pub fn simulate(
+    array: &impl Array,
+    location: &Location,
+    timestamps: impl IntoIterator<Item = i64>,
+) -> ShadingTable {
+    let mut table = ShadingTable::default();
+    for timestamp in timestamps {
+        let sun = SunPosition::at(location, timestamp);
+        if !sun.is_up() {
+            continue;
+        }
+        let Some(scene) = array.scene_at(&sun) else {
+            continue;
+        };
+
+        let direction = sun.direction();
+        let fractions = scene
+            .shading(&direction)
+            .iter()
+            .map(|shading| shading.shaded_fraction)
+            .collect();
+        let incidences = scene
+            .panels
+            .iter()
+            .map(|panel| direction.dot(&panel.plane().normal()).max(0.0))
+            .collect();
+        table.timestamps.push(timestamp);
+        table.suns.push(sun);
+        table.fractions.push(fractions);
+        table.incidences.push(incidences);
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        scene::{FixedTiltLayout, Scene},
+        simulation::{self, ShadingTable},
+        solar::{timestamp, Location, SunPosition},
+    };
+
+    #[test]
+    fn test_a_winter_day() {
+        let layout = FixedTiltLayout {
+            rows: 2,
+            panels_per_row: 1,
+            panel_width: 2.0,
+            panel_height: 2.0,
+            tilt: 30.0,
+            azimuth: 180.0,
+            pitch: 3.0,
+            clearance: 0.5,
+            gap: 0.0,
+        };
+        let scene = Scene::fixed_tilt(&layout);
+        let location = Location {
+            latitude: 48.0,
+            longitude: 0.0,
+            elevation: 0.0,
+        };
+        let start = timestamp(2022, 12, 21, 0, 0, 0);
+        let end = timestamp(2022, 12, 22, 0, 0, 0);
+
+        let table = simulation::simulate(&scene, &location, (start..end).step_by(3600));
+
+        // only daylight hours are kept
+        assert!(table.timestamps.len() > 6 && table.timestamps.len() < 12);
+        assert!(table.suns.iter().all(|sun| sun.is_up()));
+
+        // the low winter sun makes the front row shade the back one
+        let mean = table.mean_fractions();
+        assert_eq!(mean[0], 0.0);
+        assert!(mean[1] > 0.0 && mean[1] < 1.0);
+
+        // mostly around noon, when the sun hits the panels the most squarely
+        let unweighted =
+            table.fractions.iter().map(|row| row[1]).sum::<f64>() / table.fractions.len() as f64;
+        assert!(mean[1] > unweighted);
+
+        let mut csv = Vec::new();
+        table.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("timestamp,azimuth,elevation,panel_0,panel_1")
+        );
+        assert_eq!(lines.count(), table.timestamps.len());
+    }
+
+    #[test]
+    fn test_panels_lit_from_behind_carry_no_weight() {
+        let table = ShadingTable {
+            timestamps: vec![0, 3600, 7200],
+            suns: vec![
+                SunPosition {
+                    azimuth: 180.0,
+                    elevation: 30.0,
+                };
+                3
+            ],
+            fractions: vec![vec![1.0, 0.5], vec![0.0, 1.0], vec![1.0, 1.0]],
+            incidences: vec![vec![0.0, 0.5], vec![1.0, 0.0], vec![0.0, 0.0]],
+        };
+        assert_eq!(table.mean_fractions(), vec![0.0, 0.5]);
+    }
+}
@@ -0,0 +1,250 @@
+use std::f64::consts::PI;
+
+use crate::clip;
+use crate::convex;
+use crate::obstacle::ConvexSolid;
+use crate::point::Point2d;
+use crate::polygon::Polygon;
+use crate::projection::{clip_to_front, Plane};
+use crate::scene::Scene;
+use crate::vector::Vector3d;
+
+// largest angle between consecutive points along the outline of a region of the sky
+const MAX_STEP: f64 = PI / 180.0;
+
+/// Share of the isotropic diffuse light of an unobstructed sky that reaches a point on `from`
+/// (at its origin, looking towards its normal), with the ground below the horizon and
+/// `obstructions` out of the picture: 1 for a horizontal surface in the open, (1 + cos tilt) / 2
+/// for a tilted one.
+///
+/// Directions are projected onto the unit disk by dropping their component along the normal (the
+/// Nusselt analogue), where areas are proportional to the light they bring, and the area of the
+/// union of the obstructed regions is measured on the output of [`clip::sum`].
+pub fn sky_view_factor(from: &Plane, obstructions: &[&ConvexSolid]) -> f64 {
+    let mut blocked = obstructions
+        .iter()
+        .filter_map(|solid| image(from, solid))
+        .collect::<Vec<_>>();
+    blocked.extend(ground(from));
+
+    let blocked_area = clip::sum(blocked)
+        .iter()
+        .map(Polygon::signed_area)
+        .sum::<f64>();
+    (1.0 - blocked_area / PI).clamp(0.0, 1.0)
+}
+
+/// [`sky_view_factor`] at the center of every panel, obstructed by the other panels and by the
+/// obstacles of the scene
+pub fn view_factors(scene: &Scene) -> Vec<f64> {
+    let panels = scene
+        .panels
+        .iter()
+        .map(|panel| ConvexSolid::new(panel.corners().points))
+        .collect::<Vec<_>>();
+    let obstacles = scene
+        .obstacles
+        .iter()
+        .flat_map(|obstacle| obstacle.parts.iter());
+
+    scene
+        .panels
+        .iter()
+        .enumerate()
+        .map(|(index, panel)| {
+            let obstructions = panels
+                .iter()
+                .enumerate()
+                .filter(|(other_index, _)| *other_index != index)
+                .map(|(_, solid)| solid)
+                .chain(obstacles.clone())
+                .collect::<Vec<_>>();
+            sky_view_factor(&panel.plane(), &obstructions)
+        })
+        .collect()
+}
+
+// where a direction lands on the unit disk
+fn project(from: &Plane, direction: &Vector3d) -> Point2d {
+    let direction = direction.normalized();
+    Point2d::new(direction.dot(&from.u), direction.dot(&from.v))
+}
+
+// directions along the great circle from one unit vector to another, without the last one
+fn arc(start: &Vector3d, end: &Vector3d) -> Vec<Vector3d> {
+    let angle = start.dot(end).clamp(-1.0, 1.0).acos();
+    let steps = (angle / MAX_STEP).ceil().max(1.0) as usize;
+    (0..steps)
+        .map(|i| {
+            let t = i as f64 / steps as f64;
+            let (sin, sin_start, sin_end) =
+                (angle.sin(), ((1.0 - t) * angle).sin(), (t * angle).sin());
+            if sin < f64::EPSILON {
+                return start.clone();
+            }
+            &(start * (sin_start / sin)) + &(end * (sin_end / sin))
+        })
+        .collect()
+}
+
+// the directions in which the solid is seen from the plane's origin. Looking through the plane
+// at a distance of 1 along the normal (a gnomonic projection) turns great circles into lines, so
+// the convex solid shows up there as a convex polygon, whose edges are great circle arcs on the
+// hemisphere
+fn image(from: &Plane, solid: &ConvexSolid) -> Option<Polygon> {
+    let normal = from.normal();
+    let farthest = solid
+        .points
+        .iter()
+        .map(|point| Vector3d::from_points(&from.origin, point).norm())
+        .fold(0.0, f64::max);
+    let epsilon = 1e-9 * farthest;
+
+    let mut in_front = Vec::new();
+    for i in 0..solid.points.len() {
+        for j in i..solid.points.len() {
+            let pair = [solid.points[i].clone(), solid.points[j].clone()];
+            in_front.extend(clip_to_front(&pair, |point| {
+                from.height_of(point) - epsilon
+            }));
+        }
+    }
+
+    let gnomonic = in_front
+        .iter()
+        .map(|point| {
+            let from_origin = Vector3d::from_points(&from.origin, point);
+            let height = from_origin.dot(&normal);
+            Point2d::new(
+                from_origin.dot(&from.u) / height,
+                from_origin.dot(&from.v) / height,
+            )
+        })
+        .collect::<Vec<_>>();
+    let outline = convex::hull(&gnomonic)?;
+
+    let directions = outline
+        .iter_points()
+        .map(|point| &(&(&from.u * point.x) + &(&from.v * point.y)) + &normal)
+        .map(|direction| direction.normalized())
+        .collect::<Vec<_>>();
+    let n = directions.len();
+    let points = (0..n)
+        .flat_map(|i| arc(&directions[i], &directions[(i + 1) % n]))
+        .map(|direction| project(from, &direction))
+        .collect();
+    Some(Polygon::from_points(points))
+}
+
+// the part of the hemisphere that is below the horizon: up the horizon from one side of the
+// plane to the other, then back along the edge of the disk
+fn ground(from: &Plane) -> Option<Polygon> {
+    let normal = from.normal();
+    let up = Vector3d::z();
+    let sideways = normal.curl(&up);
+    if sideways.norm() < f64::EPSILON {
+        return None;
+    }
+
+    let side = sideways.normalized();
+    let mut horizon = up.curl(&side);
+    if horizon.dot(&normal) < 0.0 {
+        horizon = -horizon;
+    }
+    let mut down_the_plane = normal.curl(&side);
+    if down_the_plane.dot(&up) > 0.0 {
+        down_the_plane = -down_the_plane;
+    }
+
+    let other_side = -side.clone();
+    let mut directions = arc(&side, &horizon);
+    directions.extend(arc(&horizon, &other_side));
+    directions.extend(arc(&other_side, &down_the_plane));
+    directions.extend(arc(&down_the_plane, &side));
+
+    let points = directions
+        .iter()
+        .map(|direction| project(from, direction))
+        .collect::<Vec<_>>();
+    let ground = Polygon::from_points(points);
+    if ground.signed_area() >= 0.0 {
+        return Some(ground);
+    }
+    let mut points = ground.points;
+    points.reverse();
+    Some(Polygon::from_points(points))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        obstacle::ConvexSolid,
+        point::Point3d,
+        projection::Plane,
+        scene::{FixedTiltLayout, Scene},
+        sky::{self, sky_view_factor},
+        vector::Vector3d,
+    };
+    use std::f64::consts::PI;
+
+    fn tilted(tilt: f64) -> Plane {
+        let origin = Point3d::new(0.0, 0.0, 0.0);
+        let x = Vector3d::from_coordinates(1.0, 0.0, 0.0);
+        let (sin, cos) = tilt.to_radians().sin_cos();
+        let up_slope = Vector3d::from_coordinates(0.0, cos, sin);
+        Plane::new(origin, &x, &up_slope)
+    }
+
+    #[test]
+    fn test_unobstructed_sky() {
+        assert_eq!(sky_view_factor(&tilted(0.0), &[]), 1.0);
+        for tilt in [30.0, 60.0, 90.0] {
+            let expected = (1.0 + f64::cos(tilt * PI / 180.0)) / 2.0;
+            let actual = sky_view_factor(&tilted(tilt), &[]);
+            assert!(
+                (actual - expected).abs() < 1e-3,
+                "{tilt} {actual} {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_roof_overhead() {
+        // a 2 by 2 square 1 above the origin, made of four 1 by 1 squares with a corner above it
+        let (a, h) = (1.0, 1.0);
+        let roof = ConvexSolid::new(vec![
+            Point3d::new(-a, -a, h),
+            Point3d::new(-a, a, h),
+            Point3d::new(a, a, h),
+            Point3d::new(a, -a, h),
+        ]);
+        let x: f64 = a / h;
+        let corner = (x / (1.0 + x * x).sqrt() * (x / (1.0 + x * x).sqrt()).atan()) / PI;
+        let expected = 1.0 - 4.0 * corner;
+
+        let actual = sky_view_factor(&tilted(0.0), &[&roof]);
+        assert!((actual - expected).abs() < 1e-3, "{actual} {expected}");
+    }
+
+    #[test]
+    fn test_the_front_row_hides_some_sky_from_the_back_row() {
+        let layout = FixedTiltLayout {
+            rows: 2,
+            panels_per_row: 1,
+            panel_width: 4.0,
+            panel_height: 2.0,
+            tilt: 30.0,
+            azimuth: 180.0,
+            pitch: 3.0,
+            clearance: 0.5,
+            gap: 0.0,
+        };
+        let scene = Scene::fixed_tilt(&layout);
+
+        let factors = sky::view_factors(&scene);
+
+        let open = (1.0 + f64::cos(30.0 * PI / 180.0)) / 2.0;
+        assert!((factors[0] - open).abs() < 1e-3);
+        assert!(factors[1] < factors[0] - 1e-3);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::vector::Vector3d;
+
+/// A place on earth: latitude and longitude in degrees (north and east are positive), elevation
+/// above sea level in metres
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub elevation: f64,
+}
+
+/// Where the sun is in the sky, in degrees: azimuth clockwise from north (east is 90) and
+/// elevation above the horizon, corrected for atmospheric refraction
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SunPosition {
+    pub azimuth: f64,
+    pub elevation: f64,
+}
+
+const EARTH_MEAN_RADIUS: f64 = 6371.01; // km
+const ASTRONOMICAL_UNIT: f64 = 149597890.0; // km
+const JULIAN_DAY_OF_UNIX_EPOCH: f64 = 2440587.5;
+const JULIAN_DAY_OF_J2000: f64 = 2451545.0;
+const SECONDS_PER_DAY: i64 = 86400;
+// used for refraction, which barely changes with temperature
+const AIR_TEMPERATURE: f64 = 10.0; // °C
+
+impl SunPosition {
+    /// Sun position at `timestamp` (seconds since the unix epoch, UTC) using the PSA algorithm
+    /// (Blanco-Muriel et al. 2001), accurate to about 0.01° between 1999 and 2015 and degrading
+    /// slowly outside of that range. Topocentric parallax and refraction are accounted for, the
+    /// latter with the standard atmosphere pressure at the location's elevation.
+    pub fn at(location: &Location, timestamp: i64) -> Self {
+        let seconds_of_day = timestamp.rem_euclid(SECONDS_PER_DAY);
+        let decimal_hours = seconds_of_day as f64 / 3600.0;
+        let julian_day = timestamp as f64 / SECONDS_PER_DAY as f64 + JULIAN_DAY_OF_UNIX_EPOCH;
+        let n = julian_day - JULIAN_DAY_OF_J2000;
+
+        // ecliptic coordinates
+        let omega = 2.1429 - 0.0010394594 * n;
+        let mean_longitude = 4.8950630 + 0.017202791698 * n;
+        let mean_anomaly = 6.2400600 + 0.0172019699 * n;
+        let ecliptic_longitude = mean_longitude
+            + 0.03341607 * mean_anomaly.sin()
+            + 0.00034894 * (2.0 * mean_anomaly).sin()
+            - 0.0001134
+            - 0.0000203 * omega.sin();
+        let ecliptic_obliquity = 0.4090928 - 6.2140e-9 * n + 0.0000396 * omega.cos();
+
+        // celestial coordinates
+        let sin_ecliptic_longitude = ecliptic_longitude.sin();
+        let right_ascension = (ecliptic_obliquity.cos() * sin_ecliptic_longitude)
+            .atan2(ecliptic_longitude.cos())
+            .rem_euclid(std::f64::consts::TAU);
+        let declination = (ecliptic_obliquity.sin() * sin_ecliptic_longitude).asin();
+
+        // local coordinates
+        let greenwich_mean_sidereal_time = 6.6974243242 + 0.0657098283 * n + decimal_hours;
+        let local_mean_sidereal_time =
+            (greenwich_mean_sidereal_time * 15.0 + location.longitude).to_radians();
+        let hour_angle = local_mean_sidereal_time - right_ascension;
+        let latitude = location.latitude.to_radians();
+        let (sin_latitude, cos_latitude) = latitude.sin_cos();
+        let cos_hour_angle = hour_angle.cos();
+
+        let zenith = (cos_latitude * cos_hour_angle * declination.cos()
+            + declination.sin() * sin_latitude)
+            .acos();
+        let azimuth = (-hour_angle.sin())
+            .atan2(declination.tan() * cos_latitude - sin_latitude * cos_hour_angle)
+            .rem_euclid(std::f64::consts::TAU);
+        let parallax = EARTH_MEAN_RADIUS / ASTRONOMICAL_UNIT * zenith.sin();
+        let zenith = zenith + parallax;
+
+        let elevation = 90.0 - zenith.to_degrees();
+        Self {
+            azimuth: azimuth.to_degrees(),
+            elevation: elevation + refraction(elevation, location.elevation),
+        }
+    }
+
+    pub fn zenith(&self) -> f64 {
+        90.0 - self.elevation
+    }
+
+    pub fn is_up(&self) -> bool {
+        self.elevation > 0.0
+    }
+
+    /// Unit vector pointing towards the sun in site coordinates: x east, y north and z up
+    pub fn direction(&self) -> Vector3d {
+        let (sin_azimuth, cos_azimuth) = self.azimuth.to_radians().sin_cos();
+        let (sin_elevation, cos_elevation) = self.elevation.to_radians().sin_cos();
+        Vector3d::from_coordinates(
+            sin_azimuth * cos_elevation,
+            cos_azimuth * cos_elevation,
+            sin_elevation,
+        )
+    }
+}
+
+/// Seconds since the unix epoch of a date and time in UTC
+pub fn timestamp(year: i64, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> i64 {
+    // days from civil, counting years from March so that leap days come last
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    days * SECONDS_PER_DAY + hour as i64 * 3600 + minute as i64 * 60 + second as i64
+}
+
+// how much higher the sun appears than it is, in degrees (as in NREL's SPA)
+fn refraction(elevation: f64, altitude: f64) -> f64 {
+    let is_near_or_above_horizon = elevation >= -(0.26667 + 0.5667);
+    if !is_near_or_above_horizon {
+        return 0.0;
+    }
+    let pressure = 1013.25 * (1.0 - 2.25577e-5 * altitude).powf(5.25588); // mbar
+    let bending = 1.02 / (60.0 * (elevation + 10.3 / (elevation + 5.11)).to_radians().tan());
+    pressure / 1010.0 * 283.0 / (273.0 + AIR_TEMPERATURE) * bending
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solar::{timestamp, Location, SunPosition};
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp(1970, 1, 1, 0, 0, 0), 0);
+        assert_eq!(timestamp(2000, 3, 1, 12, 0, 0), 951912000);
+        assert_eq!(timestamp(2024, 2, 29, 23, 59, 59), 1709251199);
+    }
+
+    #[test]
+    fn test_against_the_nrel_spa_reference() {
+        // the example of the SPA report: 2003-10-17 12:30:30 at UTC-7 in Golden, Colorado
+        let location = Location {
+            latitude: 39.742476,
+            longitude: -105.1786,
+            elevation: 1830.14,
+        };
+        let sun = SunPosition::at(&location, timestamp(2003, 10, 17, 19, 30, 30));
+
+        assert!((sun.zenith() - 50.11162).abs() < 0.02, "{sun:?}");
+        assert!((sun.azimuth - 194.34024).abs() < 0.02, "{sun:?}");
+        assert!(sun.is_up());
+
+        let direction = sun.direction();
+        assert!((direction.norm() - 1.0).abs() < 1e-12);
+        // slightly west of south
+        assert!(direction.x < 0.0 && direction.y < 0.0 && direction.z > 0.0);
+    }
+
+    #[test]
+    fn test_the_sun_is_down_at_midnight() {
+        let location = Location {
+            latitude: 48.0,
+            longitude: 11.0,
+            elevation: 500.0,
+        };
+        let sun = SunPosition::at(&location, timestamp(2022, 6, 21, 23, 0, 0));
+        assert!(!sun.is_up());
+        assert!((sun.azimuth - 0.0).abs() < 20.0 || (sun.azimuth - 360.0).abs() < 20.0);
+    }
+}
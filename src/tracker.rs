@@ -0,0 +1,212 @@
+use crate::point::Point3d;
+use crate::scene::{Panel, Scene};
+use crate::solar::SunPosition;
+
+/// Rows of panels on horizontal single-axis trackers. The axes point towards `axis_azimuth`
+/// (degrees clockwise from north) at `axis_height` above the ground and are `pitch` apart; panels
+/// are `panel_width` long along the axis and `panel_height` across it, with `gap` between
+/// neighbours on the same axis. Rotations are in degrees, positive when the panels turn to face
+/// the right of the axis (east for an axis pointing north), and never exceed `max_angle`.
+#[derive(Clone, Debug)]
+pub struct SingleAxisLayout {
+    pub rows: usize,
+    pub panels_per_row: usize,
+    pub panel_width: f64,
+    pub panel_height: f64,
+    pub axis_azimuth: f64,
+    pub axis_height: f64,
+    pub pitch: f64,
+    pub gap: f64,
+    pub max_angle: f64,
+    pub backtracking: bool,
+}
+
+impl SingleAxisLayout {
+    pub fn ground_coverage_ratio(&self) -> f64 {
+        self.panel_height / self.pitch
+    }
+
+    /// The rotation that points the panels' normal as close to the sun as the axis allows,
+    /// regardless of `max_angle` and of neighbouring rows
+    pub fn true_tracking_angle(&self, sun: &SunPosition) -> f64 {
+        let zenith = sun.zenith().to_radians();
+        let across_axis = zenith.sin() * (sun.azimuth - self.axis_azimuth).to_radians().sin();
+        across_axis.atan2(zenith.cos()).to_degrees()
+    }
+
+    /// The rotation of the trackers, or `None` at night. With backtracking the panels turn back
+    /// from the sun just enough for the rows not to shade each other on flat ground (as in
+    /// Anderson and Mikofski 2020).
+    pub fn rotation(&self, sun: &SunPosition) -> Option<f64> {
+        if !sun.is_up() {
+            return None;
+        }
+
+        let mut angle = self.true_tracking_angle(sun);
+        let shades_next_row = angle.to_radians().cos() < self.ground_coverage_ratio();
+        if self.backtracking && shades_next_row {
+            let cos_correction = angle.to_radians().cos() / self.ground_coverage_ratio();
+            angle -= angle.signum() * cos_correction.acos().to_degrees();
+        }
+        Some(angle.clamp(-self.max_angle, self.max_angle))
+    }
+
+    /// The panels at the given rotation. A panel's row runs along its axis and its plane (see
+    /// [`Panel::plane`]) stays the same all day: panels face the right of the axis and are tilted
+    /// by the rotation itself, negative when they turn to the left, so a point of a panel keeps
+    /// its coordinates whichever way the panel faces.
+    pub fn scene(&self, rotation: f64) -> Scene {
+        let (sin_axis, cos_axis) = self.axis_azimuth.to_radians().sin_cos();
+        let facing = self.axis_azimuth + 90.0;
+        let first_row = (self.rows as f64 - 1.0) / 2.0;
+        let first_panel = (self.panels_per_row as f64 - 1.0) / 2.0;
+        let spacing = self.panel_width + self.gap;
+
+        let mut panels = Vec::with_capacity(self.rows * self.panels_per_row);
+        for row in 0..self.rows {
+            // rows are numbered from left to right when looking along the axes
+            let right = (row as f64 - first_row) * self.pitch;
+            for i in 0..self.panels_per_row {
+                let along = (i as f64 - first_panel) * spacing;
+                let x = sin_axis * along + cos_axis * right;
+                let y = cos_axis * along - sin_axis * right;
+                panels.push(Panel {
+                    center: Point3d::new(x, y, self.axis_height),
+                    width: self.panel_width,
+                    height: self.panel_height,
+                    tilt: rotation,
+                    azimuth: facing.rem_euclid(360.0),
+                    row,
+                });
+            }
+        }
+        Scene::new(panels)
+    }
+
+    /// The panels where the sun puts them, or `None` at night
+    pub fn scene_at(&self, sun: &SunPosition) -> Option<Scene> {
+        self.rotation(sun).map(|rotation| self.scene(rotation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        point::Point2d, polygon::Polygon, solar::SunPosition, tracker::SingleAxisLayout,
+        vector::Vector3d,
+    };
+
+    fn layout(backtracking: bool) -> SingleAxisLayout {
+        SingleAxisLayout {
+            rows: 3,
+            panels_per_row: 2,
+            panel_width: 1.0,
+            panel_height: 2.0,
+            axis_azimuth: 0.0,
+            axis_height: 1.5,
+            pitch: 5.0,
+            gap: 0.0,
+            max_angle: 60.0,
+            backtracking,
+        }
+    }
+
+    // whether any shadow reaches into the panel rather than just touching its edge
+    fn is_shaded(outline: &Polygon, shadows: &[Polygon]) -> bool {
+        shadows.iter().any(|shadow| {
+            let overlap_x = shadow.bounds.max_x.min(outline.bounds.max_x)
+                - shadow.bounds.min_x.max(outline.bounds.min_x);
+            let overlap_y = shadow.bounds.max_y.min(outline.bounds.max_y)
+                - shadow.bounds.min_y.max(outline.bounds.min_y);
+            overlap_x > 1e-9 && overlap_y > 1e-9
+        })
+    }
+
+    #[test]
+    fn test_rotation() {
+        let layout = layout(false);
+        let morning = SunPosition {
+            azimuth: 90.0,
+            elevation: 60.0,
+        };
+        assert!((layout.rotation(&morning).unwrap() - 30.0).abs() < 1e-12);
+
+        let evening = SunPosition {
+            azimuth: 270.0,
+            elevation: 20.0,
+        };
+        assert_eq!(layout.rotation(&evening), Some(-60.0));
+
+        let noon = SunPosition {
+            azimuth: 180.0,
+            elevation: 50.0,
+        };
+        assert!(layout.rotation(&noon).unwrap().abs() < 1e-12);
+
+        let night = SunPosition {
+            azimuth: 0.0,
+            elevation: -10.0,
+        };
+        assert_eq!(layout.rotation(&night), None);
+    }
+
+    #[test]
+    fn test_backtracking_avoids_row_to_row_shading() {
+        let sun = SunPosition {
+            azimuth: 90.0,
+            elevation: 15.0,
+        };
+        let direction = sun.direction();
+
+        let true_tracking = layout(false);
+        let scene = true_tracking.scene_at(&sun).unwrap();
+        let shadows = scene.shadows(&direction);
+        // the last row, furthest east, is in the sun while the others are shaded
+        let outline = scene.panels[0].outline();
+        assert!(is_shaded(&outline, &shadows[0]));
+        assert!(is_shaded(&outline, &shadows[2]));
+        assert!(!is_shaded(&outline, &shadows[4]));
+
+        let backtracking = layout(true);
+        let rotation = backtracking.rotation(&sun).unwrap();
+        assert!(rotation > 0.0 && rotation < true_tracking.true_tracking_angle(&sun));
+        let scene = backtracking.scene(rotation);
+        let shadows = scene.shadows(&direction);
+        assert!(shadows
+            .iter()
+            .zip(scene.panels.iter())
+            .all(|(shadows, panel)| !is_shaded(&panel.outline(), shadows)));
+    }
+
+    #[test]
+    fn test_panels_keep_their_frame_through_the_day() {
+        let layout = layout(false);
+        let morning = layout.scene(30.0);
+        let afternoon = layout.scene(-30.0);
+        let flat = layout.scene(0.0);
+
+        for ((morning, afternoon), flat) in morning
+            .panels
+            .iter()
+            .zip(afternoon.panels.iter())
+            .zip(flat.panels.iter())
+        {
+            assert_eq!(morning.azimuth, afternoon.azimuth);
+            // the axis is the panel's x axis, which the rotation leaves where it is
+            let (morning, afternoon, flat) = (morning.plane(), afternoon.plane(), flat.plane());
+            for x in [-0.5, 0.5] {
+                let point = Point2d::new(x, 0.0);
+                let on_axis = flat.to_world(&point);
+                assert!(Vector3d::from_points(&on_axis, &morning.to_world(&point)).norm() < 1e-12);
+                assert!(
+                    Vector3d::from_points(&on_axis, &afternoon.to_world(&point)).norm() < 1e-12
+                );
+            }
+            // and the same edge of the panel goes up in the morning and down in the afternoon
+            let edge = Point2d::new(0.0, 1.0);
+            assert!(morning.to_world(&edge).z > flat.to_world(&edge).z);
+            assert!(afternoon.to_world(&edge).z < flat.to_world(&edge).z);
+            assert!(morning.normal().z > 0.0 && afternoon.normal().z > 0.0);
+        }
+    }
+}
@@ -0,0 +1,547 @@
+use crate::point::Point2d;
+use crate::polygon::{turn, Polygon};
+use std::collections::{HashMap, HashSet};
+
+/// Triangles over the points of a polygon and its holes, ready to be handed over as an index
+/// buffer. `points` holds the polygon's points followed by the points of every hole, in the order
+/// the holes were given, and every triangle is wound the way [`Polygon`] expects.
+#[derive(Debug, Clone)]
+pub struct Triangulation {
+    pub points: Vec<Point2d>,
+    pub triangles: Vec<[usize; 3]>,
+}
+
+impl Triangulation {
+    /// Ear clipping of `polygon` minus `holes`, holes may be given in either winding
+    pub fn ear_clipping(polygon: &Polygon, holes: &[Polygon]) -> Self {
+        let mut points = polygon.points.clone();
+        let mut ring = ring_indices(0, polygon, true);
+
+        let mut hole_rings = vec![];
+        for hole in holes {
+            let hole_ring = ring_indices(points.len(), hole, false);
+            points.extend(hole.points.iter().cloned());
+            hole_rings.push(hole_ring);
+        }
+
+        // holes further to the right are bridged first so that later bridges never cross them
+        hole_rings.sort_by(|a, b| {
+            let max_x = |ring: &Vec<usize>| {
+                ring.iter()
+                    .map(|&i| points[i].x)
+                    .fold(f64::NEG_INFINITY, f64::max)
+            };
+            max_x(b).partial_cmp(&max_x(a)).unwrap()
+        });
+
+        for hole_ring in hole_rings {
+            bridge(&points, &mut ring, hole_ring);
+        }
+
+        let triangles = clip_ears(&points, ring);
+        Self { points, triangles }
+    }
+
+    /// Constrained Delaunay triangulation of `polygon` minus `holes`: the edges of the polygon and
+    /// of the holes are kept and every other edge is flipped until no triangle has a point inside
+    /// of its circumcircle, which avoids the slivers ear clipping tends to produce.
+    ///
+    /// Flipping always ends after a number of flips quadratic in the number of points, unless
+    /// rounding makes it go around in circles, which is when it gives up with [`TooManyFlips`].
+    pub fn delaunay(polygon: &Polygon, holes: &[Polygon]) -> Result<Self, TooManyFlips> {
+        let mut triangulation = Self::ear_clipping(polygon, holes);
+
+        let mut constrained_edges = HashSet::new();
+        let mut offset = 0;
+        for ring in std::iter::once(polygon).chain(holes) {
+            let n = ring.points.len();
+            for i in 0..n {
+                constrained_edges.insert(edge_key(offset + i, offset + (i + 1) % n));
+            }
+            offset += n;
+        }
+
+        let n = triangulation.points.len();
+        if triangulation.flip_until_delaunay(&constrained_edges, n * n + 1) {
+            Ok(triangulation)
+        } else {
+            Err(TooManyFlips(triangulation))
+        }
+    }
+
+    /// Flattened triangles, three indices per triangle
+    pub fn index_buffer(&self) -> Vec<usize> {
+        self.triangles.iter().flatten().copied().collect()
+    }
+
+    pub fn to_polygons(&self) -> Vec<Polygon> {
+        self.triangles
+            .iter()
+            .map(|triangle| {
+                let points = triangle.iter().map(|&i| self.points[i].clone()).collect();
+                Polygon::from_points(points)
+            })
+            .collect()
+    }
+
+    // Lawson's flip algorithm, it terminates because every flip strictly increases the smallest
+    // angle of the pair of triangles involved. Only the edges around a flip may need flipping
+    // after it, so they are all that is checked again. Whether it was done within `max_flips`.
+    fn flip_until_delaunay(
+        &mut self,
+        constrained_edges: &HashSet<(usize, usize)>,
+        max_flips: usize,
+    ) -> bool {
+        let mut edges: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (t, triangle) in self.triangles.iter().enumerate() {
+            for k in 0..3 {
+                let key = edge_key(triangle[k], triangle[(k + 1) % 3]);
+                edges.entry(key).or_default().push(t);
+            }
+        }
+
+        let mut to_check = edges.keys().copied().collect::<Vec<_>>();
+        let mut flips = 0;
+        while let Some(edge) = to_check.pop() {
+            if constrained_edges.contains(&edge) {
+                continue;
+            }
+            let Some(&[t1, t2]) = edges.get(&edge).map(Vec::as_slice) else {
+                continue;
+            };
+            let Some(flipped) = self.flip(t1, t2) else {
+                continue;
+            };
+            if flips == max_flips {
+                return false;
+            }
+            flips += 1;
+
+            // a, b, c and b, a, d became a, d, c and d, b, c
+            let [[a, d, c], [_, b, _]] = flipped;
+            self.triangles[t1] = flipped[0];
+            self.triangles[t2] = flipped[1];
+            edges.remove(&edge_key(a, b));
+            edges.insert(edge_key(c, d), vec![t1, t2]);
+            for (key, from, to) in [(edge_key(b, c), t1, t2), (edge_key(a, d), t2, t1)] {
+                if let Some(triangles) = edges.get_mut(&key) {
+                    triangles
+                        .iter_mut()
+                        .filter(|t| **t == from)
+                        .for_each(|t| *t = to);
+                }
+            }
+            to_check.extend([(a, c), (b, c), (b, d), (a, d)].map(|(x, y)| edge_key(x, y)));
+        }
+        true
+    }
+
+    fn flip(&self, t1: usize, t2: usize) -> Option<[[usize; 3]; 2]> {
+        let first = self.triangles[t1];
+        let second = self.triangles[t2];
+
+        // rotate first into a, b, c and second into b, a, d where a, b is the shared edge
+        let k = (0..3).find(|&k| {
+            let (a, b) = (first[k], first[(k + 1) % 3]);
+            (0..3).any(|l| second[l] == b && second[(l + 1) % 3] == a)
+        })?;
+        let (a, b, c) = (first[k], first[(k + 1) % 3], first[(k + 2) % 3]);
+        let d = *second.iter().find(|&&v| v != a && v != b)?;
+
+        let points = &self.points;
+        if !is_inside_of_circumcircle(&points[d], &points[a], &points[b], &points[c]) {
+            return None;
+        }
+
+        let flipped = [[a, d, c], [d, b, c]];
+        let is_convex = flipped
+            .iter()
+            .all(|&[x, y, z]| turn(&points[x], &points[y], &points[z]) > 0.0);
+        is_convex.then_some(flipped)
+    }
+}
+
+/// What [`Triangulation::delaunay`] had come to when it gave up: a valid triangulation, only not
+/// a Delaunay one everywhere
+#[derive(Debug, Clone)]
+pub struct TooManyFlips(pub Triangulation);
+
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+// strictly inside, regardless of the winding of a, b, c
+fn is_inside_of_circumcircle(point: &Point2d, a: &Point2d, b: &Point2d, c: &Point2d) -> bool {
+    let (ax, ay) = (a.x - point.x, a.y - point.y);
+    let (bx, by) = (b.x - point.x, b.y - point.y);
+    let (cx, cy) = (c.x - point.x, c.y - point.y);
+    let determinant = (ax * ax + ay * ay) * (bx * cy - cx * by)
+        - (bx * bx + by * by) * (ax * cy - cx * ay)
+        + (cx * cx + cy * cy) * (ax * by - bx * ay);
+    // the determinant is positive for points inside of triangles wound opposite to Polygon
+    determinant * -turn(a, b, c) > 0.0
+}
+
+/// indices of `polygon`'s points, offset by `offset`, in the expected winding if `outer` and in
+/// the opposite winding otherwise
+fn ring_indices(offset: usize, polygon: &Polygon, outer: bool) -> Vec<usize> {
+    let mut ring = (offset..offset + polygon.points.len()).collect::<Vec<_>>();
+    let is_expected_winding = polygon.signed_area() > 0.0;
+    if is_expected_winding != outer {
+        ring.reverse();
+    }
+    ring
+}
+
+// joins the hole to the ring through a pair of coincident edges going to and coming back from
+// the rightmost point of the hole, turning the polygon with a hole into a polygon without one
+fn bridge(points: &[Point2d], ring: &mut Vec<usize>, mut hole_ring: Vec<usize>) {
+    let rightmost = (0..hole_ring.len())
+        .max_by(|&a, &b| {
+            let a = &points[hole_ring[a]];
+            let b = &points[hole_ring[b]];
+            a.x.partial_cmp(&b.x)
+                .unwrap()
+                .then(b.y.partial_cmp(&a.y).unwrap())
+        })
+        .unwrap();
+    hole_ring.rotate_left(rightmost);
+    let m = &points[hole_ring[0]];
+
+    let visible = visible_vertex(points, ring, m);
+    let bridge_start = ring[visible];
+
+    let mut splice = hole_ring.clone();
+    splice.push(hole_ring[0]);
+    splice.push(bridge_start);
+    ring.splice(visible + 1..visible + 1, splice);
+}
+
+// position in ring of a vertex that can be joined to m without crossing any edge
+fn visible_vertex(points: &[Point2d], ring: &[usize], m: &Point2d) -> usize {
+    let n = ring.len();
+
+    // cast a ray from m towards +x and find the closest edge it hits
+    let mut closest: Option<(f64, usize)> = None;
+    for i in 0..n {
+        let a = &points[ring[i]];
+        let b = &points[ring[(i + 1) % n]];
+        let straddles = (a.y <= m.y && m.y <= b.y) || (b.y <= m.y && m.y <= a.y);
+        if !straddles || a.y == b.y {
+            continue;
+        }
+        let x = a.x + (m.y - a.y) * (b.x - a.x) / (b.y - a.y);
+        if x < m.x {
+            continue;
+        }
+        if closest.is_none_or(|(closest_x, _)| x < closest_x) {
+            closest = Some((x, i));
+        }
+    }
+
+    let (x, i) = closest.expect("a hole must lie inside of its polygon");
+    let a = ring[i];
+    let b = ring[(i + 1) % n];
+    let hit = Point2d::new(x, m.y);
+    if points[a] == hit {
+        return occurrence_facing(points, ring, i, m);
+    }
+    if points[b] == hit {
+        return occurrence_facing(points, ring, (i + 1) % n, m);
+    }
+
+    let candidate = if points[a].x > points[b].x {
+        i
+    } else {
+        (i + 1) % n
+    };
+    let p = &points[ring[candidate]];
+
+    // reflex vertices inside of the triangle m, hit, p would block the view of p, in which case the
+    // one closest in angle to the ray is visible
+    let mut best = candidate;
+    let mut best_angle = angle_to_ray(m, p);
+    for j in 0..n {
+        let prev = &points[ring[(j + n - 1) % n]];
+        let vertex = &points[ring[j]];
+        let next = &points[ring[(j + 1) % n]];
+        if j == candidate || vertex.x < m.x || turn(prev, vertex, next) > 0.0 {
+            continue;
+        }
+        if !is_inside_of_triangle(vertex, m, &hit, p) {
+            continue;
+        }
+        let angle = angle_to_ray(m, vertex);
+        let is_closer = angle < best_angle
+            || (angle == best_angle && (vertex.x - m.x).abs() < (points[ring[best]].x - m.x).abs());
+        if is_closer && is_in_cone(prev, vertex, next, m) {
+            best = j;
+            best_angle = angle;
+        }
+    }
+    occurrence_facing(points, ring, best, m)
+}
+
+// earlier bridges duplicate vertices, only one of the copies has m in front of it
+fn occurrence_facing(points: &[Point2d], ring: &[usize], position: usize, m: &Point2d) -> usize {
+    let n = ring.len();
+    let vertex = &points[ring[position]];
+    (0..n)
+        .filter(|&j| &points[ring[j]] == vertex)
+        .find(|&j| {
+            let prev = &points[ring[(j + n - 1) % n]];
+            let next = &points[ring[(j + 1) % n]];
+            is_in_cone(prev, vertex, next, m)
+        })
+        .unwrap_or(position)
+}
+
+fn angle_to_ray(m: &Point2d, p: &Point2d) -> f64 {
+    (p.y - m.y).abs().atan2(p.x - m.x)
+}
+
+// whether the direction from vertex towards point lies inside of the polygon near vertex
+fn is_in_cone(prev: &Point2d, vertex: &Point2d, next: &Point2d, point: &Point2d) -> bool {
+    if turn(prev, vertex, next) >= 0.0 {
+        turn(prev, vertex, point) > 0.0 && turn(vertex, next, point) > 0.0
+    } else {
+        turn(prev, vertex, point) > 0.0 || turn(vertex, next, point) > 0.0
+    }
+}
+
+// inside or on the boundary, regardless of the winding of a, b, c
+pub(crate) fn is_inside_of_triangle(
+    point: &Point2d,
+    a: &Point2d,
+    b: &Point2d,
+    c: &Point2d,
+) -> bool {
+    let ab = turn(a, b, point);
+    let bc = turn(b, c, point);
+    let ca = turn(c, a, point);
+    let has_negative = ab < 0.0 || bc < 0.0 || ca < 0.0;
+    let has_positive = ab > 0.0 || bc > 0.0 || ca > 0.0;
+    !(has_negative && has_positive)
+}
+
+fn clip_ears(points: &[Point2d], mut ring: Vec<usize>) -> Vec<[usize; 3]> {
+    let mut triangles = Vec::with_capacity(ring.len().saturating_sub(2));
+
+    while ring.len() > 3 {
+        let n = ring.len();
+        let ear = (0..n).find(|&i| is_ear(points, &ring, i));
+        let ear = match ear {
+            Some(ear) => ear,
+            None => {
+                // only reachable with degenerate input: drop collinear vertices first and as a
+                // last resort cut off the most convex vertex
+                let collinear = (0..n).find(|&i| {
+                    let [a, b, c] = corner(&ring, i);
+                    turn(&points[a], &points[b], &points[c]) == 0.0
+                });
+                if let Some(collinear) = collinear {
+                    ring.remove(collinear);
+                    continue;
+                }
+                (0..n)
+                    .max_by(|&i, &j| {
+                        let [a, b, c] = corner(&ring, i);
+                        let [d, e, f] = corner(&ring, j);
+                        let turn_i = turn(&points[a], &points[b], &points[c]);
+                        let turn_j = turn(&points[d], &points[e], &points[f]);
+                        turn_i.partial_cmp(&turn_j).unwrap()
+                    })
+                    .unwrap()
+            }
+        };
+        triangles.push(corner(&ring, ear));
+        ring.remove(ear);
+    }
+
+    if let [a, b, c] = ring[..] {
+        if turn(&points[a], &points[b], &points[c]) > 0.0 {
+            triangles.push([a, b, c]);
+        }
+    }
+
+    triangles
+}
+
+fn corner(ring: &[usize], i: usize) -> [usize; 3] {
+    let n = ring.len();
+    [ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]]
+}
+
+fn is_ear(points: &[Point2d], ring: &[usize], i: usize) -> bool {
+    let [a, b, c] = corner(ring, i);
+    let (pa, pb, pc) = (&points[a], &points[b], &points[c]);
+    if turn(pa, pb, pc) <= 0.0 {
+        return false;
+    }
+
+    // bridges repeat points so they are compared by value rather than by index
+    ring.iter().map(|&j| &points[j]).all(|point| {
+        point == pa || point == pb || point == pc || !is_inside_of_triangle(point, pa, pb, pc)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::ApproxEq;
+
+    fn square_with_a_hole() -> (Polygon, Polygon) {
+        let p0 = Point2d::new(0.0, 0.0);
+        let p1 = Point2d::new(0.0, 4.0);
+        let p2 = Point2d::new(4.0, 4.0);
+        let p3 = Point2d::new(4.0, 0.0);
+        let points = vec![p0, p1, p2, p3];
+        let square = Polygon::from_points(points);
+
+        let p0 = Point2d::new(1.0, 1.0);
+        let p1 = Point2d::new(2.0, 1.0);
+        let p2 = Point2d::new(2.0, 2.0);
+        let p3 = Point2d::new(1.0, 2.0);
+        let points = vec![p0, p1, p2, p3];
+        let hole = Polygon::from_points(points);
+
+        (square, hole)
+    }
+
+    #[test]
+    fn test_ear_clipping_covers_the_polygon_minus_its_hole() {
+        let (square, hole) = square_with_a_hole();
+        let triangulation = Triangulation::ear_clipping(&square, &[hole]);
+
+        assert_eq!(triangulation.points.len(), 8);
+        // n + 2h - 2 triangles for n vertices and h holes
+        assert_eq!(triangulation.triangles.len(), 8);
+        assert_eq!(triangulation.index_buffer().len(), 24);
+
+        let triangles = triangulation.to_polygons();
+        assert!(triangles
+            .iter()
+            .all(|triangle| triangle.signed_area() > 0.0));
+        let area = triangles.iter().map(|t| t.signed_area()).sum::<f64>();
+        assert!(area.approx_eq(15.0, (1e-12, 1)));
+    }
+
+    #[test]
+    fn test_delaunay_leaves_no_point_inside_of_a_circumcircle() {
+        let angles = [0.0, 0.4, 1.1, 1.5, 2.2, 2.9, 3.3, 4.0, 4.5, 5.2, 5.8];
+        let points = angles
+            .iter()
+            .map(|angle: &f64| Point2d::new(4.0 * (-angle).cos(), 2.0 * (-angle).sin()))
+            .collect();
+        let ellipse = Polygon::from_points(points);
+        assert!(ellipse.signed_area() > 0.0);
+
+        let triangulation = Triangulation::delaunay(&ellipse, &[]).unwrap();
+
+        let area = triangulation
+            .to_polygons()
+            .iter()
+            .map(|t| t.signed_area())
+            .sum::<f64>();
+        assert!(area.approx_eq(ellipse.signed_area(), (1e-12, 4)));
+
+        // every point is visible from every triangle of a convex polygon so no point may lie
+        // inside of any circumcircle
+        for &[a, b, c] in &triangulation.triangles {
+            let points = &triangulation.points;
+            for (i, point) in points.iter().enumerate() {
+                if i == a || i == b || i == c {
+                    continue;
+                }
+                let is_inside =
+                    is_inside_of_circumcircle(point, &points[a], &points[b], &points[c]);
+                assert!(!is_inside, "{point} is inside of {a} {b} {c}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_constrained_delaunay_with_holes() {
+        // a long strip, with points along its long sides, and two holes that force constrained
+        // edges through what would otherwise be Delaunay triangles
+        let mut outline = (0..=10)
+            .map(|i| Point2d::new(i as f64, 0.0))
+            .collect::<Vec<_>>();
+        outline.extend((0..=10).rev().map(|i| Point2d::new(i as f64 + 0.5, 3.0)));
+        outline.reverse();
+        let strip = Polygon::from_points(outline);
+        assert!(strip.signed_area() > 0.0);
+        let triangle = |x: f64| {
+            let p0 = Point2d::new(x, 1.0);
+            let p1 = Point2d::new(x + 2.5, 1.2);
+            let p2 = Point2d::new(x + 1.0, 2.0);
+            Polygon::from_points(vec![p0, p1, p2])
+        };
+        let holes = [triangle(1.5), triangle(6.0)];
+
+        let triangulation = Triangulation::delaunay(&strip, &holes).unwrap();
+        let ear_clipping = Triangulation::ear_clipping(&strip, &holes);
+        assert_ne!(triangulation.triangles, ear_clipping.triangles);
+
+        let area = triangulation
+            .to_polygons()
+            .iter()
+            .map(|t| t.signed_area())
+            .sum::<f64>();
+        let holes_area = holes.iter().map(|h| h.signed_area().abs()).sum::<f64>();
+        assert!(area.approx_eq(strip.signed_area() - holes_area, (1e-9, 4)));
+
+        let mut edges: HashMap<(usize, usize), Vec<[usize; 3]>> = HashMap::new();
+        for triangle in &triangulation.triangles {
+            for k in 0..3 {
+                let key = edge_key(triangle[k], triangle[(k + 1) % 3]);
+                edges.entry(key).or_default().push(*triangle);
+            }
+        }
+
+        // every edge of the rings is still there
+        let mut constrained_edges = HashSet::new();
+        let mut offset = 0;
+        for ring in std::iter::once(&strip).chain(holes.iter()) {
+            let n = ring.points.len();
+            for i in 0..n {
+                constrained_edges.insert(edge_key(offset + i, offset + (i + 1) % n));
+            }
+            offset += n;
+        }
+        assert!(constrained_edges
+            .iter()
+            .all(|edge| edges.contains_key(edge)));
+
+        // and across every other edge, the point opposite to it is outside of the circumcircle
+        // of the triangle on this side, which is what makes a constrained triangulation Delaunay
+        let points = &triangulation.points;
+        for (edge, triangles) in edges.iter() {
+            let &[first, second] = &triangles[..] else {
+                continue;
+            };
+            if constrained_edges.contains(edge) {
+                continue;
+            }
+            let d = *second.iter().find(|v| !first.contains(v)).unwrap();
+            let [a, b, c] = first;
+            let is_inside =
+                is_inside_of_circumcircle(&points[d], &points[a], &points[b], &points[c]);
+            assert!(!is_inside, "{d} is inside of {a} {b} {c}");
+        }
+    }
+
+    #[test]
+    fn test_giving_up_on_flipping() {
+        let angles = [0.0, 0.1, 0.2, 0.3, 3.0, 3.1, 3.2, 3.3];
+        let points = angles
+            .iter()
+            .map(|angle: &f64| Point2d::new(4.0 * (-angle).cos(), 2.0 * (-angle).sin()))
+            .collect();
+        let ellipse = Polygon::from_points(points);
+
+        let mut triangulation = Triangulation::ear_clipping(&ellipse, &[]);
+        assert!(!triangulation.flip_until_delaunay(&HashSet::new(), 0));
+        assert!(triangulation.flip_until_delaunay(&HashSet::new(), 100));
+    }
+}
@@ -1,6 +1,6 @@
 use std::{
     fmt::Display,
-    ops::{Neg, Sub},
+    ops::{Add, Mul, Neg, Sub},
 };
 
 use crate::{
@@ -29,6 +29,11 @@ impl Vector2d {
         self.x * vector.x + self.y * vector.y
     }
 
+    /// z component of the 3d cross product of the two vectors lying in the xy plane
+    pub fn cross(&self, vector: &Vector2d) -> f64 {
+        self.x * vector.y - self.y * vector.x
+    }
+
     pub fn norm_sq(&self) -> f64 {
         self.dot(self)
     }
@@ -58,6 +63,7 @@ impl Sub for &Vector2d {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct Vector3d {
     pub x: f64,
     pub y: f64,
@@ -112,6 +118,29 @@ impl Vector3d {
     pub fn norm_sq(&self) -> f64 {
         self.dot(self)
     }
+
+    pub fn normalized(&self) -> Vector3d {
+        self * (1.0 / self.norm())
+    }
+}
+
+impl Add for &Vector3d {
+    type Output = Vector3d;
+
+    fn add(self, other: Self) -> Self::Output {
+        let x = self.x + other.x;
+        let y = self.y + other.y;
+        let z = self.z + other.z;
+        Vector3d::from_coordinates(x, y, z)
+    }
+}
+
+impl Mul<f64> for &Vector3d {
+    type Output = Vector3d;
+
+    fn mul(self, factor: f64) -> Self::Output {
+        Vector3d::from_coordinates(self.x * factor, self.y * factor, self.z * factor)
+    }
 }
 
 impl Sub for &Vector3d {